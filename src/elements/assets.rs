@@ -0,0 +1,253 @@
+use raylib::ffi;
+use raylib::prelude::*;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+/// Textures uploaded to the GPU on a single `update` at most, so a burst of
+/// decoded images doesn't stall a frame.
+const UPLOADS_PER_UPDATE: usize = 4;
+
+/// Textures nobody uses that are kept by default, in case they're needed again.
+pub const DEFAULT_CAPACITY: usize = 64;
+
+enum AssetState {
+    /// Not requested yet, see `AssetCache::acquire_unloaded`.
+    Unloaded,
+    /// Being decoded by the worker thread.
+    Loading,
+    Ready(Texture2D),
+    Failed,
+}
+
+struct Entry {
+    state: AssetState,
+    /// Handles given out and not released yet.
+    references: usize,
+    /// Update when the texture was last drawn or acquired, for eviction.
+    last_used: Cell<u64>,
+}
+
+/// Pixels decoded by the worker thread. Raylib's `Image` holds a raw pointer
+/// and can't leave its thread, so the worker sends a copy of its pixels.
+struct DecodedImage {
+    width: i32,
+    height: i32,
+    format: i32,
+    pixels: Vec<u8>,
+}
+
+impl DecodedImage {
+    /// Loads and decodes the image file at `path`. Runs on the worker thread.
+    fn load(path: &Path) -> Result<DecodedImage, String> {
+        let image = Image::load_image(&path.to_string_lossy())?;
+        let size = unsafe { ffi::GetPixelDataSize(image.width, image.height, image.format) };
+        if image.data.is_null() || size <= 0 {
+            return Err(String::from("image has no pixels"));
+        }
+        // Copied before `image` is dropped, which frees its data.
+        let pixels = unsafe { std::slice::from_raw_parts(image.data as *const u8, size as usize) }.to_vec();
+        Ok(DecodedImage {
+            width: image.width,
+            height: image.height,
+            format: image.format,
+            pixels,
+        })
+    }
+
+    /// Uploads the pixels to the GPU. Raylib only reads them while copying,
+    /// so they stay owned by `self`.
+    fn upload(&mut self, _thread: &RaylibThread) -> Result<Texture2D, String> {
+        let raw = ffi::Image {
+            data: self.pixels.as_mut_ptr() as *mut c_void,
+            width: self.width,
+            height: self.height,
+            mipmaps: 1,
+            format: self.format,
+        };
+        let texture = unsafe { ffi::LoadTextureFromImage(raw) };
+        if texture.id == 0 {
+            return Err(String::from("can't upload texture to the GPU"));
+        }
+        Ok(unsafe { Texture2D::from_raw(texture) })
+    }
+}
+
+/// A reference to a texture of `AssetCache`. It can't be cloned: every handle
+/// counts as a reference, given back with `AssetCache::release`.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct TextureHandle {
+    path: PathBuf,
+}
+
+/// ## Asset Cache
+/// Loads textures from disk without blocking the render loop. Image files are
+/// read and decoded on a worker thread, and their pixels uploaded to the GPU on the main thread by
+/// `update`, which should be called once per frame.
+///
+/// Textures are shared by path and counted: `acquire` gives a handle, and
+/// `release` gives it back. Textures without handles stay cached until there are
+/// more than `capacity` of them, when the least recently used are dropped.
+/// While a texture loads, or when it can't be loaded, a placeholder is drawn
+/// instead, so scenes never have to deal with missing textures:
+///
+/// ```rust
+/// let handle = assets.acquire(Path::new("covers/zelda.png"));
+/// d.draw_texture(assets.get(&handle), 0, 0, Color::WHITE);
+/// assets.release(handle);
+/// ```
+pub struct AssetCache {
+    entries: HashMap<PathBuf, Entry>,
+    placeholder: Texture2D,
+    requests: Sender<PathBuf>,
+    decoded: Receiver<(PathBuf, Result<DecodedImage, String>)>,
+    capacity: usize,
+    updates: u64,
+}
+
+impl AssetCache {
+    /// Creates the cache and its worker thread. Fails if the placeholder
+    /// texture can't be uploaded.
+    pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> Result<AssetCache, String> {
+        let checked = Image::gen_image_checked(64, 64, 8, 8, Color::LIGHTGRAY, Color::GRAY);
+        let placeholder = rl.load_texture_from_image(thread, &checked)?;
+
+        let (requests, worker_requests) = mpsc::channel::<PathBuf>();
+        let (worker_decoded, decoded) = mpsc::channel();
+        // The worker stops once the cache is dropped, with its sender.
+        thread::spawn(move || {
+            for path in worker_requests {
+                let image = DecodedImage::load(&path);
+                if worker_decoded.send((path, image)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(AssetCache {
+            entries: HashMap::new(),
+            placeholder,
+            requests,
+            decoded,
+            capacity: DEFAULT_CAPACITY,
+            updates: 0,
+        })
+    }
+
+    /// Gets a handle to the texture at `path`, starting to load it if it isn't
+    /// cached.
+    pub fn acquire(&mut self, path: &Path) -> TextureHandle {
+        let updates = self.updates;
+        let requests = &self.requests;
+        let entry = self.entries.entry(path.to_path_buf()).or_insert_with(|| {
+            let state = match requests.send(path.to_path_buf()) {
+                Ok(()) => AssetState::Loading,
+                Err(_) => AssetState::Failed,
+            };
+            Entry {
+                state,
+                references: 0,
+                last_used: Cell::new(updates),
+            }
+        });
+        entry.references += 1;
+        entry.last_used.set(updates);
+
+        TextureHandle {
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// Gets a handle to the texture at `path` without loading it, for files
+    /// that are still being made. The placeholder is drawn until `reload` is
+    /// called.
+    pub fn acquire_unloaded(&mut self, path: &Path) -> TextureHandle {
+        let updates = self.updates;
+        let entry = self.entries.entry(path.to_path_buf()).or_insert_with(|| Entry {
+            state: AssetState::Unloaded,
+            references: 0,
+            last_used: Cell::new(updates),
+        });
+        entry.references += 1;
+        entry.last_used.set(updates);
+
+        TextureHandle {
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// Loads the file of `handle` again, like when it changed on disk.
+    pub fn reload(&mut self, handle: &TextureHandle) {
+        if let Some(entry) = self.entries.get_mut(&handle.path) {
+            entry.state = match self.requests.send(handle.path.clone()) {
+                Ok(()) => AssetState::Loading,
+                Err(_) => AssetState::Failed,
+            };
+        }
+    }
+
+    /// Gives a handle back. The texture stays cached, but can be evicted once
+    /// no handle refers to it.
+    pub fn release(&mut self, handle: TextureHandle) {
+        if let Some(entry) = self.entries.get_mut(&handle.path) {
+            entry.references = entry.references.saturating_sub(1);
+        }
+    }
+
+    /// Texture of `handle`, or the placeholder if it isn't loaded.
+    pub fn get(&self, handle: &TextureHandle) -> &Texture2D {
+        match self.entries.get(&handle.path) {
+            Some(entry) => {
+                entry.last_used.set(self.updates);
+                match &entry.state {
+                    AssetState::Ready(texture) => texture,
+                    _ => &self.placeholder,
+                }
+            }
+            None => &self.placeholder,
+        }
+    }
+
+    /// Uploads some of the images decoded since last call and evicts textures
+    /// over capacity.
+    pub fn update(&mut self, _rl: &mut RaylibHandle, thread: &RaylibThread) {
+        self.updates += 1;
+
+        for (path, image) in self.decoded.try_iter().take(UPLOADS_PER_UPDATE) {
+            // Evicted while decoding.
+            let entry = match self.entries.get_mut(&path) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            entry.state = match image.and_then(|mut image| image.upload(thread)) {
+                Ok(texture) => AssetState::Ready(texture),
+                Err(err) => {
+                    println!("can't load texture {}: {}", path.display(), err);
+                    AssetState::Failed
+                }
+            };
+        }
+        self.evict();
+    }
+
+    /// Drops the least recently used textures without handles, keeping
+    /// `capacity` of them.
+    fn evict(&mut self) {
+        let mut unused: Vec<(PathBuf, u64)> = self.entries.iter()
+            .filter(|(_, entry)| entry.references == 0)
+            .map(|(path, entry)| (path.clone(), entry.last_used.get()))
+            .collect();
+        if unused.len() <= self.capacity {
+            return;
+        }
+
+        unused.sort_by_key(|(_, last_used)| *last_used);
+        let excess = unused.len() - self.capacity;
+        for (path, _) in unused.into_iter().take(excess) {
+            self.entries.remove(&path);
+        }
+    }
+}
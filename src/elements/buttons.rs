@@ -1,3 +1,5 @@
+use crate::elements::{Action, InputState};
+use raylib::ease::{self, EaseFn};
 use raylib::prelude::*;
 
 #[derive(PartialEq, Debug, Copy, Clone)]
@@ -5,6 +7,29 @@ pub enum States {
     Idle = 0,
     Active,
     Down,
+    /// Selected by keyboard or gamepad navigation.
+    Focused,
+}
+
+/// What happened to a button on a frame, as returned by `Button::update`.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum ButtonEvent {
+    /// Mouse went down over the button, or Confirm was pressed while focused.
+    Pressed,
+    /// The press that started on this button ended, wherever it ended.
+    Released,
+    /// The press ended still over (or focused on) the button: this is the one
+    /// scenes usually react to.
+    Clicked,
+    HoverEntered,
+    HoverLeft,
+}
+
+/// What started the current press of a button.
+#[derive(PartialEq, Debug, Copy, Clone)]
+enum PressSource {
+    Mouse,
+    Confirm,
 }
 
 #[derive(Copy, Clone)]
@@ -17,6 +42,19 @@ pub struct ButtonStyle {
 
     pub down_color: Color,
     pub down_background: Color,
+
+    pub focused_color: Color,
+    pub focused_background: Color,
+
+    /// Size of the button on each state, relative to its `size`.
+    pub hovering_scale: f32,
+    pub down_scale: f32,
+    pub focused_scale: f32,
+
+    /// Seconds it takes to go from a state to another, and the curve followed.
+    /// A duration of `0.0` switches instantly.
+    pub transition_duration: f32,
+    pub transition_easing: EaseFn,
 }
 
 pub struct Button {
@@ -24,17 +62,31 @@ pub struct Button {
     pub size: Vector2,
     pub is_active: bool,
     pub is_down: bool,
-    pub label: &'static str,
+    pub is_focused: bool,
+    pub label: String,
+    /// Identifies what the button does, so scenes can dispatch on it. Defaults to
+    /// the label in lowercase.
+    pub action: String,
     pub style: ButtonStyle,
     pub color: Color,
     pub background: Color,
+    /// Current scale, animated towards the one of the style for `state`.
+    pub scale: f32,
     
     state: States,
     last_state: States,
+    /// Progress of the transition from `last_state` to `state`, from 0.0 to 1.0.
     fraction: f32,
+    /// Looks of the button when the transition started, so interrupting one
+    /// doesn't snap back to `last_state`.
+    from_color: Color,
+    from_background: Color,
+    from_scale: f32,
+    pressed_by: Option<PressSource>,
 }
 
 impl ButtonStyle {
+    #[allow(clippy::redundant_field_names)]
     pub fn from_default(
         idle_color: Color, 
         idle_background: Color,
@@ -49,6 +101,15 @@ impl ButtonStyle {
             down_color: idle_color,
             down_background: idle_background,
 
+            focused_color: idle_color,
+            focused_background: idle_background,
+
+            hovering_scale: 1.0,
+            down_scale: 1.0,
+            focused_scale: 1.0,
+
+            transition_duration: 0.15,
+            transition_easing: ease::cubic_out,
         }
     }
 
@@ -62,23 +123,45 @@ impl ButtonStyle {
         self
     }
 
-    pub fn edit_hover_style(
+    pub fn edit_focus_style(
         &mut self,
-        hover_color: Color,
-        hover_background: Color,
+        focused_color: Color,
+        focused_background: Color,
     ) -> &mut ButtonStyle {
-        self.hovering_color = hover_color;
-        self.hovering_background = hover_background;
+        self.focused_color = focused_color;
+        self.focused_background = focused_background;
         self
     }
 
-    pub fn edit_idle_style(
+    pub fn edit_scales(
         &mut self,
-        idle_color: Color,
-        idle_background: Color,
+        hovering_scale: f32,
+        down_scale: f32,
+        focused_scale: f32,
+    ) -> &mut ButtonStyle {
+        self.hovering_scale = hovering_scale;
+        self.down_scale = down_scale;
+        self.focused_scale = focused_scale;
+        self
+    }
+
+    pub fn edit_transition(
+        &mut self,
+        duration: f32,
+        easing: EaseFn,
     ) -> &mut ButtonStyle {
-        self.idle_color = idle_color;
-        self.idle_background = idle_background;
+        self.transition_duration = duration;
+        self.transition_easing = easing;
+        self
+    }
+
+    pub fn edit_hover_style(
+        &mut self,
+        hover_color: Color,
+        hover_background: Color,
+    ) -> &mut ButtonStyle {
+        self.hovering_color = hover_color;
+        self.hovering_background = hover_background;
         self
     }
 
@@ -93,69 +176,163 @@ impl ButtonStyle {
             down_color: Color::DARKBLUE,
             down_background: Color::GRAY,
 
+            focused_color: Color::WHITE,
+            focused_background: Color::DARKGRAY,
+
+            hovering_scale: 1.05,
+            down_scale: 0.95,
+            focused_scale: 1.05,
+
+            transition_duration: 0.15,
+            transition_easing: ease::cubic_out,
+        }
+    }
+
+    /// Text color, background and scale of `state`.
+    fn looks(&self, state: States) -> (Color, Color, f32) {
+        match state {
+            States::Idle => (self.idle_color, self.idle_background, 1.0),
+            States::Active => (self.hovering_color, self.hovering_background, self.hovering_scale),
+            States::Focused => (self.focused_color, self.focused_background, self.focused_scale),
+            States::Down => (self.down_color, self.down_background, self.down_scale),
         }
     }
 }
 
 impl Button {
 
-    pub fn new(position: Vector2, size: Vector2, label: &'static str, button_style: ButtonStyle) -> Button {
+    #[allow(clippy::redundant_field_names)]
+    pub fn new(position: Vector2, size: Vector2, label: &str, button_style: ButtonStyle) -> Button {
         Button {
             position: position,
             size: size,
             is_active: false,
             is_down: false,
+            is_focused: false,
             state: States::Idle,
-            label: label,
+            label: String::from(label),
+            action: label.to_lowercase(),
             style: button_style,
             color: button_style.idle_color,
             background: button_style.idle_background,
-            fraction: 0.0,
-            last_state: States::Idle
+            scale: 1.0,
+            fraction: 1.0,
+            last_state: States::Idle,
+            from_color: button_style.idle_color,
+            from_background: button_style.idle_background,
+            from_scale: 1.0,
+            pressed_by: None,
         }
     }
 
-    pub fn draw(&mut self, drawing_context: &mut RaylibDrawHandle, dt: f32){
-        let center_x = self.position.x + self.size.x / 2.0;
-        let center_y = self.position.y + self.size.y / 2.0;
-        let label_x = center_x as i32 - measure_text(self.label, 20) / 2;
-        let label_y = center_y as i32 -  20 / 2;
+    /// Sets the action id of the button.
+    pub fn with_action(mut self, action: &str) -> Button {
+        self.action = String::from(action);
+        self
+    }
 
+    /// Reads `input` and updates hover, focus and press states, returning what
+    /// happened on this frame. A click needs the press to start and end on the
+    /// button: dragging the mouse out before releasing cancels it.
+    pub fn update(&mut self, input: &InputState, focused: bool) -> Vec<ButtonEvent> {
+        let mut events = vec![];
+        let hovered = self.is_hover(input.mouse_position);
 
-        if self.is_active && self.is_down {
-            self.state = States::Down
+        if hovered && !self.is_active {
+            events.push(ButtonEvent::HoverEntered);
+        } else if !hovered && self.is_active {
+            events.push(ButtonEvent::HoverLeft);
         }
+        self.is_active = hovered;
+        self.is_focused = focused;
 
-        else if self.is_active {
-            self.state = States::Active
+        if self.pressed_by.is_none() {
+            if hovered && input.mouse_pressed {
+                self.pressed_by = Some(PressSource::Mouse);
+                events.push(ButtonEvent::Pressed);
+            } else if focused && input.pressed(Action::Confirm) {
+                self.pressed_by = Some(PressSource::Confirm);
+                events.push(ButtonEvent::Pressed);
+            }
         }
 
-        else {
-            self.state = States::Idle
+        let (released, inside) = match self.pressed_by {
+            Some(PressSource::Mouse) => (!input.mouse_down, hovered),
+            Some(PressSource::Confirm) => (!input.held(Action::Confirm), focused),
+            None => (false, false),
+        };
+        if released {
+            self.pressed_by = None;
+            events.push(ButtonEvent::Released);
+            if inside {
+                events.push(ButtonEvent::Clicked);
+            }
         }
 
-        match self.state {
-            States::Active => {
-                self.color = self.style.hovering_color;
-                self.background = self.style.hovering_background;
-            }
+        self.is_down = self.pressed_by.is_some() && inside;
+        events
+    }
 
-            States::Down => {
-                self.color = self.style.down_color;
-                self.background = self.style.down_background;
-            }
+    /// Moves the looks towards the state left by `update`. Call it on every
+    /// scene update, with `dt` as the seconds since last one.
+    pub fn animate(&mut self, dt: f32) {
+        let state = if (self.is_active || self.is_focused) && self.is_down {
+            States::Down
+        } else if self.is_focused {
+            States::Focused
+        } else if self.is_active {
+            States::Active
+        } else {
+            States::Idle
+        };
 
-            States::Idle => {
-                self.color = self.style.idle_color;
-                self.background = self.style.idle_background
-            }
+        if state != self.state {
+            self.from_color = self.color;
+            self.from_background = self.background;
+            self.from_scale = self.scale;
+            self.last_state = self.state;
+            self.state = state;
+            self.fraction = 0.0;
+        }
+
+        let duration = self.style.transition_duration;
+        self.fraction = if duration > 0.0 {
+            (self.fraction + dt / duration).min(1.0)
+        } else {
+            1.0
         };
+        let t = (self.style.transition_easing)(self.fraction, 0.0, 1.0, 1.0);
 
-        drawing_context.draw_rectangle(self.position.x as i32, self.position.y as i32, self.size.x as i32, self.size.y as i32, self.background);
-        drawing_context.draw_text(self.label, label_x, label_y, 20, self.color);
+        let (color, background, scale) = self.style.looks(self.state);
+        self.color = lerp_color(self.from_color, color, t);
+        self.background = lerp_color(self.from_background, background, t);
+        self.scale = self.from_scale + (scale - self.from_scale) * t;
+    }
 
+    /// Draws the button with the looks reached by `animate`.
+    pub fn draw(&self, drawing_context: &mut RaylibDrawHandle){
+        let center_x = self.position.x + self.size.x / 2.0;
+        let center_y = self.position.y + self.size.y / 2.0;
+        let width = self.size.x * self.scale;
+        let height = self.size.y * self.scale;
+        let font_size = (20.0 * self.scale).round() as i32;
+        let label_x = center_x as i32 - measure_text(&self.label, font_size) / 2;
+        let label_y = center_y as i32 - font_size / 2;
+
+        drawing_context.draw_rectangle(
+            (center_x - width / 2.0) as i32, (center_y - height / 2.0) as i32,
+            width as i32, height as i32,
+            self.background
+        );
+        drawing_context.draw_text(&self.label, label_x, label_y, font_size, self.color);
+    }
+
+    /// Area taken by the button on screen.
+    pub fn bounds(&self) -> Rectangle {
+        Rectangle::new(self.position.x, self.position.y, self.size.x, self.size.y)
     }
 
+    #[allow(clippy::needless_return)]
     pub fn is_hover(&self, position: Vector2) -> bool {
         if position.x >= self.position.x && position.x <= self.position.x + self.size.x {
             return position.y >= self.position.y && position.y <= self.position.y + self.size.y
@@ -164,4 +341,9 @@ impl Button {
         }
     }
 
-}
\ No newline at end of file
+}
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Color::new(channel(from.r, to.r), channel(from.g, to.g), channel(from.b, to.b), channel(from.a, to.a))
+}
@@ -0,0 +1,548 @@
+use crate::elements::{Action, Binding, CoverFit, InputMap};
+use raylib::core::input::key_from_i32;
+use raylib::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use toml::value::{Table, Value};
+
+/// Version of the config file layout. Older files are upgraded through
+/// `MIGRATIONS` when opened.
+pub const CONFIG_VERSION: u32 = 2;
+
+/// Smallest window size accepted from the config file.
+const MIN_WINDOW_SIZE: (i32, i32) = (320, 240);
+const MAX_FPS: u32 = 1000;
+/// Gamepads raylib can read.
+const MAX_GAMEPADS: i32 = 4;
+/// Most columns the grid view can be given.
+pub const MAX_GRID_COLUMNS: usize = 12;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+    UnsupportedVersion(u32),
+    /// A setting with a value that can't be used, found by `Config::repair`.
+    Invalid { setting: String, reason: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "config file error: {}", err),
+            ConfigError::Parse(err) => write!(f, "malformed config file: {}", err),
+            ConfigError::Serialize(err) => write!(f, "can't serialize config: {}", err),
+            ConfigError::UnsupportedVersion(v) => write!(
+                f, "config version {} isn't supported (1 to {})", v, CONFIG_VERSION
+            ),
+            ConfigError::Invalid { setting, reason } => write!(f, "config setting {} {}", setting, reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(err: toml::de::Error) -> Self {
+        ConfigError::Parse(err)
+    }
+}
+
+impl From<toml::ser::Error> for ConfigError {
+    fn from(err: toml::ser::Error) -> Self {
+        ConfigError::Serialize(err)
+    }
+}
+
+/// Window settings. `title`, `resizable` and `vsync` are only read when the
+/// window opens.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct WindowConfig {
+    pub title: String,
+    pub width: i32,
+    pub height: i32,
+    pub resizable: bool,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    /// Frames per second cap. `0` means uncapped.
+    pub fps: u32,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            title: String::from("Starframe"),
+            width: 640,
+            height: 480,
+            resizable: true,
+            fullscreen: false,
+            vsync: false,
+            fps: 60,
+        }
+    }
+}
+
+/// Input settings, applied over `InputMap::default_bindings`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct InputConfig {
+    /// Index of the gamepad to read, from 0 to 3.
+    pub gamepad: i32,
+    /// How far an axis must be pushed to count as pressed, from 0.0 to 1.0.
+    pub dead_zone: f32,
+    /// Keyboard keys of an action, by action name (like `page_left`) and raylib
+    /// key code. They replace the default keys of that action only.
+    pub keys: BTreeMap<String, Vec<i32>>,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        InputConfig {
+            gamepad: 0,
+            dead_zone: 0.5,
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+impl InputConfig {
+    /// Default bindings with these settings applied. Unknown actions and key
+    /// codes are skipped, and so is a gamepad raylib can't read.
+    pub fn input_map(&self) -> InputMap {
+        let mut map = InputMap::default_bindings();
+        if let Some(gamepad) = gamepad_number(self.gamepad) {
+            map.gamepad = gamepad;
+        }
+        map.dead_zone = self.dead_zone;
+        for (name, codes) in &self.keys {
+            let action = match Action::from_name(name) {
+                Some(action) => action,
+                None => continue,
+            };
+            map.clear_keys(action);
+            for key in codes.iter().filter_map(|code| key_from_i32(*code)) {
+                map.bind(action, Binding::Key(key));
+            }
+        }
+        map
+    }
+}
+
+/// Raylib gamepad of a `gamepad` setting, if it's one raylib can read.
+fn gamepad_number(index: i32) -> Option<GamepadNumber> {
+    match index {
+        0 => Some(GamepadNumber::GAMEPAD_PLAYER1),
+        1 => Some(GamepadNumber::GAMEPAD_PLAYER2),
+        2 => Some(GamepadNumber::GAMEPAD_PLAYER3),
+        3 => Some(GamepadNumber::GAMEPAD_PLAYER4),
+        _ => None,
+    }
+}
+
+/// Library settings, read when Starframe starts.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct LibraryConfig {
+    /// Scan library folders for new and missing games at startup.
+    pub scan_on_startup: bool,
+    /// Start the menu on the grid of covers instead of a single cover.
+    pub grid_view: bool,
+    pub grid_columns: usize,
+    /// How artwork is fitted into the covers of the menu.
+    pub cover_fit: CoverFit,
+}
+
+impl Default for LibraryConfig {
+    fn default() -> Self {
+        LibraryConfig {
+            scan_on_startup: true,
+            grid_view: false,
+            grid_columns: 4,
+            cover_fit: CoverFit::default(),
+        }
+    }
+}
+
+/// Colors used by scenes for their backgrounds and text.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub fn background(&self) -> Color {
+        match self {
+            Theme::Light => Color::RAYWHITE,
+            Theme::Dark => Color::new(24, 24, 28, 255),
+        }
+    }
+
+    /// Bars and panels drawn over the background.
+    pub fn panel(&self) -> Color {
+        match self {
+            Theme::Light => Color::WHITE,
+            Theme::Dark => Color::new(44, 44, 52, 255),
+        }
+    }
+
+    pub fn text(&self) -> Color {
+        match self {
+            Theme::Light => Color::BLACK,
+            Theme::Dark => Color::RAYWHITE,
+        }
+    }
+
+    /// Secondary text, like hints and labels.
+    pub fn faded_text(&self) -> Color {
+        match self {
+            Theme::Light => Color::DARKGRAY,
+            Theme::Dark => Color::LIGHTGRAY,
+        }
+    }
+}
+
+/// Plugin settings. Only read when plugins are loaded, at startup.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct PluginsConfig {
+    /// Names of the plugins that aren't loaded.
+    pub disabled: Vec<String>,
+}
+
+/// On-disk layout of the config file. Plain values go before tables, as TOML
+/// requires.
+#[derive(Serialize, Deserialize)]
+struct ConfigFile {
+    version: u32,
+    #[serde(default)]
+    theme: Theme,
+    #[serde(default)]
+    window: WindowConfig,
+    #[serde(default)]
+    input: InputConfig,
+    #[serde(default)]
+    library: LibraryConfig,
+    #[serde(default)]
+    plugins: PluginsConfig,
+}
+
+/// Upgrades a file from each old version to the next: `MIGRATIONS[0]` turns a
+/// version 1 file in a version 2 one. They work on plain TOML, since old
+/// layouts have no structs left.
+const MIGRATIONS: [fn(&mut Table); CONFIG_VERSION as usize - 1] = [migrate_v1_to_v2];
+
+/// Version 2 renamed `[display]` to `[window]`, which also holds the title and
+/// resizable flag now.
+fn migrate_v1_to_v2(file: &mut Table) {
+    if let Some(display) = file.remove("display") {
+        file.insert(String::from("window"), display);
+    }
+}
+
+/// ## Config
+/// User settings, changed on the Options scene or by hand. It's stored as a
+/// versioned TOML file, by default on `$XDG_CONFIG_HOME/starframe/config.toml`,
+/// where missing settings take their default values:
+///
+/// ```rust
+/// let mut config = Config::open_default()?;
+/// for problem in config.repair() {
+///     println!("{}", problem);
+/// }
+/// config.theme = Theme::Dark;
+/// config.save()?;
+/// ```
+///
+/// Files from older versions are upgraded when opened. Changes only reach the
+/// disk when `save` is called, and never on a read-only config; `is_outdated`
+/// tells when the file should be written back even without changes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    path: PathBuf,
+    /// The file is missing, older than `CONFIG_VERSION` or had invalid settings.
+    outdated: bool,
+    read_only: bool,
+    pub theme: Theme,
+    pub window: WindowConfig,
+    pub input: InputConfig,
+    pub library: LibraryConfig,
+    pub plugins: PluginsConfig,
+}
+
+impl Config {
+    /// Creates a config with default settings that will be saved on `path`.
+    pub fn new(path: PathBuf) -> Config {
+        Config {
+            path,
+            outdated: true,
+            read_only: false,
+            theme: Theme::default(),
+            window: WindowConfig::default(),
+            input: InputConfig::default(),
+            library: LibraryConfig::default(),
+            plugins: PluginsConfig::default(),
+        }
+    }
+
+    /// Default location of the config file.
+    pub fn default_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("starframe")
+            .join("config.toml")
+    }
+
+    /// Opens the config on default location. See `Config::open`.
+    pub fn open_default() -> Result<Config, ConfigError> {
+        Config::open(Config::default_path())
+    }
+
+    /// Reads the config stored on `path`, upgrading it if it's from an older
+    /// version. A missing file isn't an error, it just gives the default
+    /// settings. Neither is a missing `version`, taken as the current one, so
+    /// files can be written by hand. Values aren't checked, see `repair`.
+    pub fn open(path: PathBuf) -> Result<Config, ConfigError> {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Config::new(path)),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut table: Table = toml::from_str(&content)?;
+        let version = match table.get("version").and_then(Value::as_integer) {
+            Some(version) => u32::try_from(version).unwrap_or(0),
+            None => CONFIG_VERSION,
+        };
+        if version == 0 || version > CONFIG_VERSION {
+            return Err(ConfigError::UnsupportedVersion(version));
+        }
+        for migrate in &MIGRATIONS[version as usize - 1..] {
+            migrate(&mut table);
+        }
+        table.insert(String::from("version"), Value::Integer(CONFIG_VERSION as i64));
+        let file: ConfigFile = Value::Table(table).try_into()?;
+
+        Ok(Config {
+            path,
+            outdated: version < CONFIG_VERSION,
+            read_only: false,
+            theme: file.theme,
+            window: file.window,
+            input: file.input,
+            library: file.library,
+            plugins: file.plugins,
+        })
+    }
+
+    /// Puts invalid settings back to their defaults, returning what was wrong
+    /// with each. Unknown actions and key codes are dropped from the input
+    /// settings instead.
+    pub fn repair(&mut self) -> Vec<ConfigError> {
+        let mut problems = vec![];
+        let mut invalid = |setting: &str, reason: &str| problems.push(ConfigError::Invalid {
+            setting: setting.to_string(),
+            reason: reason.to_string(),
+        });
+        let (window, input, library) = (WindowConfig::default(), InputConfig::default(), LibraryConfig::default());
+
+        if self.window.title.trim().is_empty() {
+            invalid("window.title", "can't be empty");
+            self.window.title = window.title;
+        }
+        if self.window.width < MIN_WINDOW_SIZE.0 || self.window.height < MIN_WINDOW_SIZE.1 {
+            invalid("window.width/height", &format!("must be at least {}x{}", MIN_WINDOW_SIZE.0, MIN_WINDOW_SIZE.1));
+            self.window.width = window.width;
+            self.window.height = window.height;
+        }
+        if self.window.fps > MAX_FPS {
+            invalid("window.fps", &format!("must be {} or less", MAX_FPS));
+            self.window.fps = window.fps;
+        }
+
+        if gamepad_number(self.input.gamepad).is_none() {
+            invalid("input.gamepad", &format!("must be from 0 to {}", MAX_GAMEPADS - 1));
+            self.input.gamepad = input.gamepad;
+        }
+        if !(0.0..=1.0).contains(&self.input.dead_zone) {
+            invalid("input.dead_zone", "must be from 0.0 to 1.0");
+            self.input.dead_zone = input.dead_zone;
+        }
+        self.input.keys.retain(|name, codes| {
+            let setting = format!("input.keys.{}", name);
+            if Action::from_name(name).is_none() {
+                invalid(&setting, "isn't an action");
+                return false;
+            }
+            let count = codes.len();
+            codes.retain(|code| key_from_i32(*code).is_some());
+            if codes.len() < count {
+                invalid(&setting, "has unknown key codes");
+            }
+            // Keys removed above shouldn't leave the action without keyboard.
+            count == 0 || !codes.is_empty()
+        });
+
+        if !(1..=MAX_GRID_COLUMNS).contains(&self.library.grid_columns) {
+            invalid("library.grid_columns", &format!("must be from 1 to {}", MAX_GRID_COLUMNS));
+            self.library.grid_columns = library.grid_columns;
+        }
+
+        self.outdated |= !problems.is_empty();
+        problems
+    }
+
+    /// Whether the file was missing, older or had settings `repair` fixed when
+    /// opened, so it should be written back.
+    pub fn is_outdated(&self) -> bool {
+        self.outdated
+    }
+
+    /// Makes `save` leave the file untouched, like when it failed to load and
+    /// default settings stand in for it.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Writes the config back to its file, through a temporary file like
+    /// `GameLibrary::save`.
+    pub fn save(&self) -> Result<(), ConfigError> {
+        if self.read_only {
+            return Ok(());
+        }
+        let file = ConfigFile {
+            version: CONFIG_VERSION,
+            theme: self.theme,
+            window: self.window.clone(),
+            input: self.input.clone(),
+            library: self.library.clone(),
+            plugins: self.plugins.clone(),
+        };
+        let content = toml::to_string(&file)?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp_path = self.path.with_extension("toml.tmp");
+        fs::write(&temp_path, content)?;
+        fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Config file on an empty folder on the temp dir, unique to `name`.
+    fn temp_config(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("starframe-config-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("config.toml")
+    }
+
+    #[test]
+    fn missing_file_gives_defaults() {
+        let path = temp_config("missing");
+        let config = Config::open(path.clone()).unwrap();
+        assert_eq!(config, Config::new(path));
+        assert!(config.is_outdated());
+    }
+
+    #[test]
+    fn migrates_display_to_window() {
+        let path = temp_config("v1");
+        fs::write(&path, "version = 1\ntheme = \"dark\"\n\n[display]\nwidth = 1024\nheight = 768\nfullscreen = true\n").unwrap();
+
+        let config = Config::open(path).unwrap();
+        assert!(config.is_outdated());
+        assert_eq!(config.theme, Theme::Dark);
+        assert_eq!((config.window.width, config.window.height), (1024, 768));
+        assert!(config.window.fullscreen);
+        assert_eq!(config.window.title, WindowConfig::default().title);
+    }
+
+    #[test]
+    fn current_version_isnt_outdated() {
+        let path = temp_config("v2");
+        fs::write(&path, format!("version = {}\n\n[window]\nwidth = 800\nheight = 600\n", CONFIG_VERSION)).unwrap();
+
+        let config = Config::open(path).unwrap();
+        assert!(!config.is_outdated());
+        assert_eq!(config.window.width, 800);
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        let path = temp_config("unsupported");
+        for version in [0, CONFIG_VERSION + 1] {
+            fs::write(&path, format!("version = {}\n", version)).unwrap();
+            match Config::open(path.clone()) {
+                Err(ConfigError::UnsupportedVersion(v)) => assert_eq!(v, version),
+                other => panic!("version {} opened as {:?}", version, other),
+            }
+        }
+    }
+
+    #[test]
+    fn saves_and_opens_again() {
+        let path = temp_config("round-trip");
+        let mut config = Config::new(path.clone());
+        config.theme = Theme::Dark;
+        config.window.fps = 0;
+        config.input.keys.insert(String::from("confirm"), vec![KeyboardKey::KEY_SPACE as i32]);
+        config.save().unwrap();
+
+        let mut opened = Config::open(path).unwrap();
+        assert!(!opened.is_outdated());
+        opened.outdated = true;
+        assert_eq!(opened, config);
+    }
+
+    #[test]
+    fn repair_resets_invalid_settings() {
+        let mut config = Config::new(temp_config("repair"));
+        config.outdated = false;
+        config.window.title = String::from(" ");
+        config.window.width = 100;
+        config.window.fps = MAX_FPS + 1;
+        config.input.gamepad = MAX_GAMEPADS;
+        config.input.dead_zone = 1.5;
+        config.input.keys.insert(String::from("jump"), vec![KeyboardKey::KEY_SPACE as i32]);
+        config.input.keys.insert(String::from("back"), vec![-1]);
+        config.input.keys.insert(String::from("menu"), vec![-1, KeyboardKey::KEY_M as i32]);
+        config.library.grid_columns = 0;
+
+        let problems = config.repair();
+        assert_eq!(problems.len(), 9);
+        assert!(config.is_outdated());
+
+        let mut expected = Config::new(config.path.clone());
+        expected.input.keys.insert(String::from("menu"), vec![KeyboardKey::KEY_M as i32]);
+        assert_eq!(config, expected);
+        assert!(config.repair().is_empty());
+    }
+
+    #[test]
+    fn input_map_reads_the_gamepad() {
+        let mut input = InputConfig { gamepad: 2, ..InputConfig::default() };
+        assert_eq!(input.input_map().gamepad, GamepadNumber::GAMEPAD_PLAYER3);
+        input.gamepad = 7;
+        assert_eq!(input.input_map().gamepad, GamepadNumber::GAMEPAD_PLAYER1);
+    }
+}
@@ -0,0 +1,54 @@
+use anymap::AnyMap;
+
+/// ## App Context
+/// Services shared by every scene, like the game library, the input map or the
+/// plugin host. It holds a single value of each type, so services are fetched by
+/// type:
+///
+/// ```rust
+/// let mut context = AppContext::new();
+/// context.insert(GameLibrary::open_default()?);
+///
+/// // Later, on any scene:
+/// if let Some(library) = context.get::<GameLibrary>() {
+///     println!("{} games", library.games().len());
+/// }
+/// ```
+///
+/// `SceneManager` owns the context and hands it to scenes on `load`, `update`
+/// and `unload`. Anything can insert its own services too, wrapping them in a
+/// new type when the type alone doesn't say what they are.
+pub struct AppContext {
+    resources: AnyMap,
+}
+
+impl AppContext {
+    pub fn new() -> AppContext {
+        AppContext {
+            resources: AnyMap::new(),
+        }
+    }
+
+    /// Adds a service, returning the one of the same type it replaced.
+    pub fn insert<T: 'static>(&mut self, resource: T) -> Option<T> {
+        self.resources.insert(resource)
+    }
+
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.resources.get::<T>()
+    }
+
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.resources.get_mut::<T>()
+    }
+
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.resources.remove::<T>()
+    }
+}
+
+impl Default for AppContext {
+    fn default() -> Self {
+        AppContext::new()
+    }
+}
@@ -1,102 +1,345 @@
+use crate::elements::{AssetCache, TextureHandle};
 use raylib::prelude::*;
+use serde::{Deserialize, Serialize};
 
 pub struct Cover {
-    pub img: Texture2D,
+    /// Artwork, drawn from the `AssetCache` it was acquired from.
+    pub img: TextureHandle,
     pub label: String,
-    pub origin: Vector2,
     pub pos: Vector2,
     pub size: Vector2,
-    pub rotation: f32
+    pub rotation: f32,
+    /// How the artwork is fitted into `pos` and `size`.
+    pub fit: CoverFit,
+    /// Library id of the game shown by this cover.
+    pub game_id: Option<u64>,
 }
 
+/// Seconds taken by the carousel to slide from a cover to the next.
+const SLIDE_DURATION: f32 = 0.35;
+
+/// ## Cover Book
+/// Carousel of covers where one of them is selected. Paging with `next` and
+/// `previous` wraps around the ends and slides the covers, so `update` must be
+/// called every frame to advance the animation.
 pub struct CoverBook{
     pub covers: Vec<Cover>,
+    selected: usize,
+    /// Cover shown before the last page, while it slides away.
+    leaving: Option<usize>,
+    /// -1.0 when paging to the previous cover, 1.0 to the next one.
+    direction: f32,
+    elapsed: f32,
 }
 
 impl CoverBook{
     pub fn new() -> CoverBook {
         CoverBook {
-            covers: Vec::new()
+            covers: Vec::new(),
+            selected: 0,
+            leaving: None,
+            direction: 0.0,
+            elapsed: SLIDE_DURATION,
         }
     }
 
     pub fn insert_cover(&mut self, cover: Cover){
         self.covers.push(cover);
     }
+
+    /// Fits the artwork of every cover with `fit`.
+    pub fn set_fit(&mut self, fit: CoverFit) {
+        for cover in &mut self.covers {
+            cover.fit = fit;
+        }
+    }
+
+    /// Removes every cover, giving their textures back to `assets`.
+    pub fn clear(&mut self, assets: &mut AssetCache) {
+        for cover in self.covers.drain(..) {
+            assets.release(cover.img);
+        }
+        self.selected = 0;
+        self.leaving = None;
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_cover(&self) -> Option<&Cover> {
+        self.covers.get(self.selected)
+    }
+
+    /// Selects the cover at `index` without animation.
+    pub fn select(&mut self, index: usize) {
+        if index < self.covers.len() {
+            self.selected = index;
+            self.leaving = None;
+            self.elapsed = SLIDE_DURATION;
+        }
+    }
+
+    pub fn next(&mut self) {
+        if !self.covers.is_empty() {
+            self.page_to((self.selected + 1) % self.covers.len(), 1.0);
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if !self.covers.is_empty() {
+            self.page_to((self.selected + self.covers.len() - 1) % self.covers.len(), -1.0);
+        }
+    }
+
+    fn page_to(&mut self, index: usize, direction: f32) {
+        if index == self.selected {
+            return;
+        }
+        self.leaving = Some(self.selected);
+        self.selected = index;
+        self.direction = direction;
+        self.elapsed = 0.0;
+    }
+
+    /// Advances the slide animation by `dt` seconds.
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed = (self.elapsed + dt).min(SLIDE_DURATION);
+        if self.elapsed >= SLIDE_DURATION {
+            self.leaving = None;
+        }
+    }
+
+    /// Draws the selected cover (and the one leaving, while sliding) with its
+    /// label on top of the cover area.
+    pub fn draw(&self, d: &mut RaylibDrawHandle, assets: &AssetCache, screen_size: Vector2) {
+        let cover = match self.selected_cover() {
+            Some(cover) => cover,
+            None => return,
+        };
+
+        // Goes from 1.0 to 0.0: how far the selected cover still is from the center.
+        let remaining = 1.0 - ease::cubic_out(self.elapsed, 0.0, 1.0, SLIDE_DURATION);
+        let shift = screen_size.x * self.direction;
+        let scale = 1.0 - 0.2 * (remaining * std::f32::consts::PI).sin();
+
+        if let Some(leaving) = self.leaving.and_then(|index| self.covers.get(index)) {
+            leaving.draw_at(d, assets, (remaining - 1.0) * shift, scale);
+        }
+        cover.draw_at(d, assets, remaining * shift, scale);
+        cover.draw_lines(d);
+
+        let label_width = measure_text(&cover.label, 20);
+        let label_x = screen_size.x as i32 / 2 - label_width / 2;
+        d.draw_rectangle(label_x - 10, 90, label_width + 20, 30, Color::BLACK.fade(0.6));
+        d.draw_text(&cover.label, label_x, 95, 20, Color::WHITE);
+    }
 }
 
 impl Cover {
-    pub fn new(image: Texture2D, text_label: String, origin: Vector2, pos: Vector2, size: Vector2) -> Cover {
+    #[allow(clippy::redundant_field_names)]
+    pub fn new(image: TextureHandle, text_label: String, pos: Vector2, size: Vector2) -> Cover {
         Cover{
             img: image,
             label: text_label,
-            origin: origin,
             pos: pos,
             size: size,
-            rotation: 0.0
+            rotation: 0.0,
+            fit: CoverFit::default(),
+            game_id: None,
         }
     }
-    
-    pub fn draw(&self, d: &mut RaylibDrawHandle, screen_size: Vector2){
-        let img_rec = Rectangle::new(0.0, 0.0, self.img.width as f32, self.img.height as f32);
 
-        if img_rec.width == img_rec.height {
-            let mut dest_size = screen_size.x;
-            if screen_size.y < dest_size {
-                dest_size = screen_size.y
-            }
+    /// Rectangle the cover is fitted into, given by `pos` and `size`.
+    pub fn bounds(&self) -> Rectangle {
+        Rectangle::new(self.pos.x, self.pos.y, self.size.x, self.size.y)
+    }
+    
+    /// Draws the cover fitted into its bounds, moved `offset_x` pixels sideways
+    /// and resized by `scale` around its center. The placeholder of `assets` is
+    /// drawn while the artwork loads.
+    pub fn draw_at(&self, d: &mut RaylibDrawHandle, assets: &AssetCache, offset_x: f32, scale: f32){
+        let texture = assets.get(&self.img);
+        let image_size = Vector2::new(texture.width as f32, texture.height as f32);
+        let (source, dest) = self.fit.layout(image_size, self.bounds());
 
-            dest_size -= 80.0;
-            let dest_rectangle = Rectangle::new(
-                screen_size.x / 2.0, 
-                screen_size.y / 2.0 + 40.0, 
-                dest_size, 
-                dest_size
-            );
-            let origin = Vector2::new(dest_rectangle.width / 2.0, dest_rectangle.height / 2.0);
-            d.draw_texture_pro(
-                &self.img, 
-                img_rec, 
-                dest_rectangle, 
-                origin, 
-                self.rotation, 
-                Color::WHITE
-            )
-        } else {
-            let mut width_scale = screen_size.x / img_rec.width;
-            let mut height_scale: f32;
-            if img_rec.height * width_scale > screen_size.y {
-                height_scale = screen_size.y / img_rec.height;
-            } else {
-                height_scale = width_scale;
-            }
+        // Drawn around its center, so scale and rotation keep it in place.
+        let dest_rectangle = Rectangle::new(
+            dest.x + dest.width / 2.0 + offset_x,
+            dest.y + dest.height / 2.0,
+            dest.width * scale,
+            dest.height * scale
+        );
+        let origin = Vector2::new(dest_rectangle.width / 2.0, dest_rectangle.height / 2.0);
+        d.draw_texture_pro(
+            texture, 
+            source, 
+            dest_rectangle, 
+            origin, 
+            self.rotation, 
+            Color::WHITE
+        )
+    }
 
-            width_scale -= 0.016;
-            height_scale -= 0.05;
-
-            let dest_rectangle = Rectangle::new(
-                screen_size.x / 2.0, 
-                screen_size.y / 2.0 + 40.0, 
-                width_scale * img_rec.width,
-                height_scale * img_rec.height
-            );
-            let origin = Vector2::new(dest_rectangle.width / 2.0, dest_rectangle.height / 2.0);
-            d.draw_texture_pro(
-                &self.img, 
-                img_rec, 
-                dest_rectangle, 
-                origin, 
-                self.rotation, 
-                Color::WHITE
-            )
-        }
+    /// Draws the cover fitted into `bounds` instead of its own, like for a
+    /// thumbnail. Takes any draw mode, so it can be clipped by scissor mode.
+    pub fn draw_into(&self, d: &mut impl RaylibDraw, assets: &AssetCache, bounds: Rectangle){
+        let texture = assets.get(&self.img);
+        let image_size = Vector2::new(texture.width as f32, texture.height as f32);
+        let (source, dest) = self.fit.layout(image_size, bounds);
+        d.draw_texture_pro(texture, source, dest, Vector2::new(0.0, 0.0), 0.0, Color::WHITE)
     }
 
     pub fn draw_lines(&self, d: &mut RaylibDrawHandle){
         d.draw_rectangle_lines_ex(
-            Rectangle::new(self.pos.x, self.pos.y, self.size.x, self.size.y), 
+            self.bounds(), 
             3, 
             Color::RED
         )
     }
-}
\ No newline at end of file
+}
+
+/// ## Cover Fit
+/// How a cover's artwork is fitted into its bounds. Every mode but `Stretch`
+/// keeps the artwork's aspect ratio, so portrait box art, landscape banners
+/// and square CD art are all laid out the same way. Covers of the menu use the
+/// `library.cover_fit` setting.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverFit {
+    /// Whole artwork, as big as it fits, centered. Leaves empty bands on the
+    /// sides or on top and bottom.
+    #[default]
+    Contain,
+    /// Fills the bounds, cropping the artwork's edges that don't fit.
+    Crop,
+    /// Fills the bounds, distorting the artwork.
+    Stretch,
+    /// Fills the bounds' height. The artwork is centered sideways, and cropped
+    /// on the sides when it's wider than the bounds.
+    FixedHeight,
+}
+
+impl CoverFit {
+    pub const ALL: [CoverFit; 4] = [CoverFit::Contain, CoverFit::Crop, CoverFit::Stretch, CoverFit::FixedHeight];
+
+    /// Part of an image of `image_size` to draw, and where to draw it for it to
+    /// fit on `target`.
+    pub fn layout(&self, image_size: Vector2, target: Rectangle) -> (Rectangle, Rectangle) {
+        let full = Rectangle::new(0.0, 0.0, image_size.x, image_size.y);
+        if image_size.x <= 0.0 || image_size.y <= 0.0 {
+            return (full, target);
+        }
+
+        let scale = match self {
+            CoverFit::Stretch => return (full, target),
+            CoverFit::Contain => (target.width / image_size.x).min(target.height / image_size.y),
+            CoverFit::Crop => (target.width / image_size.x).max(target.height / image_size.y),
+            CoverFit::FixedHeight => target.height / image_size.y,
+        };
+
+        // Drawn size, and the part of the image it shows once cropped to target.
+        let width = (image_size.x * scale).min(target.width);
+        let height = (image_size.y * scale).min(target.height);
+        let source = Rectangle::new(
+            (image_size.x - width / scale) / 2.0,
+            (image_size.y - height / scale) / 2.0,
+            width / scale,
+            height / scale
+        );
+        let dest = Rectangle::new(
+            target.x + (target.width - width) / 2.0,
+            target.y + (target.height - height) / 2.0,
+            width,
+            height
+        );
+        (source, dest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Rect = [f32; 4];
+
+    const TARGET: Rect = [10.0, 20.0, 200.0, 100.0];
+
+    /// Source and destination of an image of `width` and `height` fitted into
+    /// `TARGET`, rounded to avoid float noise.
+    fn layout(fit: CoverFit, width: f32, height: f32) -> (Rect, Rect) {
+        let [x, y, w, h] = TARGET;
+        let (source, dest) = fit.layout(Vector2::new(width, height), Rectangle::new(x, y, w, h));
+        let round = |r: Rectangle| [r.x, r.y, r.width, r.height].map(|v| (v * 1000.0).round() / 1000.0);
+        (round(source), round(dest))
+    }
+
+    #[test]
+    fn contain_keeps_the_whole_image() {
+        // Portrait box art: as tall as the target, centered sideways.
+        assert_eq!(layout(CoverFit::Contain, 300.0, 400.0), (
+            [0.0, 0.0, 300.0, 400.0],
+            [72.5, 20.0, 75.0, 100.0],
+        ));
+        // Landscape banner: as wide as the target, centered vertically.
+        assert_eq!(layout(CoverFit::Contain, 800.0, 200.0), (
+            [0.0, 0.0, 800.0, 200.0],
+            [10.0, 45.0, 200.0, 50.0],
+        ));
+        // Square CD art.
+        assert_eq!(layout(CoverFit::Contain, 50.0, 50.0), (
+            [0.0, 0.0, 50.0, 50.0],
+            [60.0, 20.0, 100.0, 100.0],
+        ));
+    }
+
+    #[test]
+    fn crop_fills_the_target() {
+        // Portrait: the top and bottom are cropped.
+        assert_eq!(layout(CoverFit::Crop, 300.0, 400.0), (
+            [0.0, 125.0, 300.0, 150.0],
+            TARGET,
+        ));
+        // Landscape: the sides are cropped.
+        assert_eq!(layout(CoverFit::Crop, 800.0, 200.0), (
+            [200.0, 0.0, 400.0, 200.0],
+            TARGET,
+        ));
+        assert_eq!(layout(CoverFit::Crop, 50.0, 50.0), (
+            [0.0, 12.5, 50.0, 25.0],
+            TARGET,
+        ));
+    }
+
+    #[test]
+    fn stretch_fills_the_target_with_the_whole_image() {
+        for (width, height) in [(300.0, 400.0), (800.0, 200.0), (50.0, 50.0)] {
+            assert_eq!(layout(CoverFit::Stretch, width, height), ([0.0, 0.0, width, height], TARGET));
+        }
+    }
+
+    #[test]
+    fn fixed_height_crops_only_the_sides() {
+        assert_eq!(layout(CoverFit::FixedHeight, 300.0, 400.0), (
+            [0.0, 0.0, 300.0, 400.0],
+            [72.5, 20.0, 75.0, 100.0],
+        ));
+        assert_eq!(layout(CoverFit::FixedHeight, 800.0, 200.0), (
+            [200.0, 0.0, 400.0, 200.0],
+            TARGET,
+        ));
+        assert_eq!(layout(CoverFit::FixedHeight, 50.0, 50.0), (
+            [0.0, 0.0, 50.0, 50.0],
+            [60.0, 20.0, 100.0, 100.0],
+        ));
+    }
+
+    #[test]
+    fn zero_size_images_fill_the_target() {
+        for fit in CoverFit::ALL {
+            for (width, height) in [(0.0, 0.0), (0.0, 100.0), (100.0, 0.0)] {
+                assert_eq!(layout(fit, width, height), ([0.0, 0.0, width, height], TARGET));
+            }
+        }
+    }
+}
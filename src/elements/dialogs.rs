@@ -0,0 +1,158 @@
+use crate::elements::{Action, AppContext, AsScene, Button, ButtonEvent, ButtonStyle, FocusGroup, InputState, SceneCommand};
+use raylib::prelude::*;
+
+/// ## Exit Dialog
+/// Overlay asking whether to quit, pushed by `SceneManager` when an exit is
+/// requested and `SceneManager::set_confirm_exit` is on. "No" (or Back) closes
+/// it, going back to the scene below.
+pub struct ExitDialog {
+    buttons: [Button; 2],
+    focus: FocusGroup,
+}
+
+/// Name the dialog is registered with on `SceneManager`.
+pub const EXIT_DIALOG: &str = "Exit Dialog";
+
+const DIALOG_SIZE: Vector2 = Vector2 { x: 300.0, y: 140.0 };
+
+impl ExitDialog {
+    pub fn new() -> ExitDialog {
+        let size = Vector2::new(120.0, 40.0);
+        ExitDialog {
+            buttons: [
+                Button::new(Vector2::new(0.0, 0.0), size, "Yes", ButtonStyle::build_default_style()),
+                Button::new(Vector2::new(0.0, 0.0), size, "No", ButtonStyle::build_default_style()),
+            ],
+            focus: FocusGroup::new(),
+        }
+    }
+
+    fn area(screen_size: Vector2) -> Rectangle {
+        Rectangle::new(
+            (screen_size.x - DIALOG_SIZE.x) / 2.0,
+            (screen_size.y - DIALOG_SIZE.y) / 2.0,
+            DIALOG_SIZE.x,
+            DIALOG_SIZE.y
+        )
+    }
+}
+
+impl Default for ExitDialog {
+    fn default() -> Self {
+        ExitDialog::new()
+    }
+}
+
+impl AsScene for ExitDialog {
+    fn name(&self) -> &'static str {
+        EXIT_DIALOG
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn load(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread, _context: &mut AppContext) {
+        // Keyboard and gamepad users start on "No", so a double press doesn't quit.
+        self.focus.set_areas(self.buttons.iter().map(Button::bounds).collect());
+        self.focus.focus(1);
+    }
+
+    fn update(&mut self, rl: &mut RaylibHandle, _thread: &RaylibThread, dt: f32, input: &InputState, _context: &mut AppContext) -> SceneCommand {
+        let area = ExitDialog::area(Vector2::new(rl.get_screen_width() as f32, rl.get_screen_height() as f32));
+        for (index, button) in self.buttons.iter_mut().enumerate() {
+            button.position = Vector2::new(
+                area.x + 20.0 + index as f32 * (button.size.x + 20.0),
+                area.y + area.height - button.size.y - 20.0
+            );
+        }
+
+        self.focus.set_areas(self.buttons.iter().map(Button::bounds).collect());
+        if input.mouse_moved {
+            self.focus.clear();
+        }
+        if let Some(direction) = input.direction() {
+            self.focus.navigate(direction);
+        }
+        if input.pressed(Action::Back) {
+            return SceneCommand::pop_scene();
+        }
+
+        let focused = self.focus.focused();
+        for (index, button) in self.buttons.iter_mut().enumerate() {
+            let events = button.update(input, focused == Some(index));
+            button.animate(dt);
+            if !events.contains(&ButtonEvent::Clicked) {
+                continue;
+            }
+            match button.action.as_str() {
+                "yes" => return SceneCommand::confirm_exit(),
+                _ => return SceneCommand::pop_scene(),
+            }
+        }
+        SceneCommand::continue_program()
+    }
+
+    fn draw(&mut self, d: &mut RaylibDrawHandle, _context: &AppContext) {
+        let area = ExitDialog::area(Vector2::new(d.get_screen_width() as f32, d.get_screen_height() as f32));
+        let message = "Quit Starframe?";
+
+        d.draw_rectangle_rec(area, Color::RAYWHITE);
+        d.draw_rectangle_lines_ex(area, 3, Color::BLACK);
+        d.draw_text(
+            message,
+            (area.x + area.width / 2.0) as i32 - measure_text(message, 20) / 2,
+            area.y as i32 + 25,
+            20,
+            Color::BLACK
+        );
+
+        for button in &self.buttons {
+            button.draw(d);
+        }
+    }
+
+    fn unload(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread, _context: &mut AppContext) {}
+}
+
+/// ## Error Scene
+/// Shown by `SceneManager` when a scene can't be played, like when a command
+/// names a scene that doesn't exist, instead of crashing. Confirm or Back
+/// leaves it, going back to the scene below or to the first scene.
+pub struct ErrorScene {
+    message: String,
+}
+
+impl ErrorScene {
+    pub fn new(message: String) -> ErrorScene {
+        ErrorScene { message }
+    }
+}
+
+impl AsScene for ErrorScene {
+    fn name(&self) -> &'static str {
+        "Error"
+    }
+
+    fn load(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread, _context: &mut AppContext) {}
+
+    fn update(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread, _dt: f32, input: &InputState, _context: &mut AppContext) -> SceneCommand {
+        if input.pressed(Action::Confirm) || input.pressed(Action::Back) || input.mouse_pressed {
+            return SceneCommand::pop_scene();
+        }
+        SceneCommand::continue_program()
+    }
+
+    fn draw(&mut self, d: &mut RaylibDrawHandle, _context: &AppContext) {
+        let (s_wid, s_heig) = (d.get_screen_width(), d.get_screen_height());
+        let title = "Something went wrong";
+        let hint = "Press Confirm to go back";
+
+        d.clear_background(Color::MAROON);
+        d.draw_text(title, s_wid / 2 - measure_text(title, 30) / 2, s_heig / 2 - 60, 30, Color::WHITE);
+        d.draw_text(&self.message, s_wid / 2 - measure_text(&self.message, 20) / 2, s_heig / 2, 20, Color::WHITE);
+        d.draw_text(hint, s_wid / 2 - measure_text(hint, 20) / 2, s_heig - 40, 20, Color::LIGHTGRAY);
+    }
+
+    fn unload(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread, _context: &mut AppContext) {}
+}
@@ -0,0 +1,463 @@
+use crate::elements::GameEntry;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Version of the emulators file layout.
+pub const EMULATORS_VERSION: u32 = 1;
+
+/// Placeholders accepted by argument templates.
+///
+/// - `{rom}`: full path of the game file;
+/// - `{rom_dir}`: folder holding the game file;
+/// - `{rom_name}`: file name without extension;
+/// - `{title}`: title registered on the library.
+pub const PLACEHOLDERS: [&str; 4] = ["rom", "rom_dir", "rom_name", "title"];
+
+#[derive(Debug)]
+pub enum EmulatorError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+    UnsupportedVersion(u32),
+    DuplicatedProfile(String),
+    MissingPlaceholder { profile: String, placeholder: &'static str },
+    UnknownPlaceholder { profile: String, placeholder: String },
+    MalformedTemplate { profile: String, reason: &'static str },
+    ExecutableNotFound { profile: String, executable: PathBuf },
+}
+
+impl fmt::Display for EmulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EmulatorError::Io(err) => write!(f, "emulators file error: {}", err),
+            EmulatorError::Parse(err) => write!(f, "malformed emulators file: {}", err),
+            EmulatorError::Serialize(err) => write!(f, "can't serialize emulators: {}", err),
+            EmulatorError::UnsupportedVersion(v) => write!(
+                f, "emulators version {} is newer than supported ({})", v, EMULATORS_VERSION
+            ),
+            EmulatorError::DuplicatedProfile(name) => write!(f, "emulator \"{}\" already exists", name),
+            EmulatorError::MissingPlaceholder { profile, placeholder } => write!(
+                f, "arguments of \"{}\" need a {{{}}} placeholder", profile, placeholder
+            ),
+            EmulatorError::UnknownPlaceholder { profile, placeholder } => write!(
+                f, "arguments of \"{}\" use unknown placeholder {{{}}}", profile, placeholder
+            ),
+            EmulatorError::MalformedTemplate { profile, reason } => write!(
+                f, "arguments of \"{}\" are malformed: {}", profile, reason
+            ),
+            EmulatorError::ExecutableNotFound { profile, executable } => write!(
+                f, "executable of \"{}\" not found: {}", profile, executable.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EmulatorError {}
+
+impl From<io::Error> for EmulatorError {
+    fn from(err: io::Error) -> Self {
+        EmulatorError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for EmulatorError {
+    fn from(err: toml::de::Error) -> Self {
+        EmulatorError::Parse(err)
+    }
+}
+
+impl From<toml::ser::Error> for EmulatorError {
+    fn from(err: toml::ser::Error) -> Self {
+        EmulatorError::Serialize(err)
+    }
+}
+
+/// ## Emulator Profile
+/// Describes how to run games of some platform with an emulator:
+///
+/// ```toml
+/// [[emulator]]
+/// name = "Mupen64Plus"
+/// executable = "mupen64plus"
+/// arguments = "--fullscreen {rom}"
+/// platforms = ["n64"]
+/// extensions = ["z64", "n64", "v64"]
+///
+/// [emulator.env]
+/// SDL_VIDEODRIVER = "x11"
+/// ```
+///
+/// `arguments` is split like a shell would (quotes group words) before the
+/// placeholders are replaced, so paths with spaces stay as a single argument.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EmulatorProfile {
+    pub name: String,
+    pub executable: PathBuf,
+    pub arguments: String,
+    #[serde(default)]
+    pub platforms: Vec<String>,
+    /// File extensions without the leading dot.
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+impl EmulatorProfile {
+    pub fn new(name: &str, executable: PathBuf, arguments: &str) -> EmulatorProfile {
+        EmulatorProfile {
+            name: String::from(name),
+            executable,
+            arguments: String::from(arguments),
+            platforms: vec![],
+            extensions: vec![],
+            working_dir: None,
+            env: BTreeMap::new(),
+        }
+    }
+
+    pub fn supports_platform(&self, platform: &str) -> bool {
+        self.platforms.iter().any(|p| p.eq_ignore_ascii_case(platform))
+    }
+
+    pub fn supports_file(&self, path: &Path) -> bool {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(extension) => self.extensions.iter().any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(extension)),
+            None => false,
+        }
+    }
+
+    /// Checks the argument template and looks up the executable.
+    pub fn validate(&self) -> Result<(), EmulatorError> {
+        let words = self.template_words()?;
+        let uses_rom = words.iter().flatten().any(|piece| *piece == Piece::Placeholder(String::from("rom")));
+        if !uses_rom {
+            return Err(EmulatorError::MissingPlaceholder {
+                profile: self.name.clone(),
+                placeholder: "rom",
+            });
+        }
+
+        self.find_executable().map(|_| ())
+    }
+
+    /// Builds the command line that runs `game` with this emulator.
+    pub fn build_command(&self, game: &GameEntry) -> Result<Command, EmulatorError> {
+        self.validate()?;
+        let executable = self.find_executable()?;
+
+        let mut command = Command::new(executable);
+        for word in self.template_words()? {
+            let argument: String = word.iter().map(|piece| match piece {
+                Piece::Text(text) => text.clone(),
+                Piece::Placeholder(name) => placeholder_value(name, game),
+            }).collect();
+            command.arg(argument);
+        }
+
+        match &self.working_dir {
+            Some(folder) => { command.current_dir(folder); }
+            None => {
+                if let Some(folder) = game.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    command.current_dir(folder);
+                }
+            }
+        }
+        command.envs(&self.env);
+        Ok(command)
+    }
+
+    /// Resolves `executable` as a path, or searches it on `PATH` when it's a bare name.
+    pub fn find_executable(&self) -> Result<PathBuf, EmulatorError> {
+        let not_found = || EmulatorError::ExecutableNotFound {
+            profile: self.name.clone(),
+            executable: self.executable.clone(),
+        };
+
+        if self.executable.components().count() > 1 {
+            return if self.executable.is_file() { Ok(self.executable.clone()) } else { Err(not_found()) };
+        }
+
+        let search_path = env::var_os("PATH").ok_or_else(not_found)?;
+        env::split_paths(&search_path)
+            .map(|folder| folder.join(&self.executable))
+            .find(|candidate| candidate.is_file())
+            .ok_or_else(not_found)
+    }
+
+    /// Splits `arguments` in words made of text and placeholders.
+    fn template_words(&self) -> Result<Vec<Vec<Piece>>, EmulatorError> {
+        let malformed = |reason| EmulatorError::MalformedTemplate {
+            profile: self.name.clone(),
+            reason,
+        };
+
+        let mut words = vec![];
+        let mut word: Vec<Piece> = vec![];
+        let mut text = String::new();
+        let mut quote: Option<char> = None;
+        let mut in_word = false;
+        let mut chars = self.arguments.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' | '\'' if quote.is_none() => { quote = Some(c); in_word = true; }
+                c if Some(c) == quote => quote = None,
+                c if c.is_whitespace() && quote.is_none() => {
+                    if in_word {
+                        if !text.is_empty() { word.push(Piece::Text(std::mem::take(&mut text))); }
+                        words.push(std::mem::take(&mut word));
+                        in_word = false;
+                    }
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(malformed("unclosed '{'")),
+                        }
+                    }
+                    if !PLACEHOLDERS.contains(&name.as_str()) {
+                        return Err(EmulatorError::UnknownPlaceholder {
+                            profile: self.name.clone(),
+                            placeholder: name,
+                        });
+                    }
+                    if !text.is_empty() { word.push(Piece::Text(std::mem::take(&mut text))); }
+                    word.push(Piece::Placeholder(name));
+                    in_word = true;
+                }
+                '}' => return Err(malformed("unexpected '}'")),
+                c => { text.push(c); in_word = true; }
+            }
+        }
+
+        if quote.is_some() {
+            return Err(malformed("unclosed quote"));
+        }
+        if in_word {
+            if !text.is_empty() { word.push(Piece::Text(text)); }
+            words.push(word);
+        }
+        Ok(words)
+    }
+}
+
+#[derive(PartialEq, Debug)]
+enum Piece {
+    Text(String),
+    Placeholder(String),
+}
+
+fn placeholder_value(name: &str, game: &GameEntry) -> String {
+    let lossy = |path: Option<&std::ffi::OsStr>| path.map(|p| p.to_string_lossy().into_owned()).unwrap_or_default();
+    match name {
+        "rom" => game.path.to_string_lossy().into_owned(),
+        "rom_dir" => lossy(game.path.parent().map(|p| p.as_os_str())),
+        "rom_name" => lossy(game.path.file_stem()),
+        "title" => game.title.clone(),
+        _ => String::new(),
+    }
+}
+
+/// On-disk layout of the emulators file.
+#[derive(Serialize, Deserialize)]
+struct EmulatorsFile {
+    version: u32,
+    #[serde(default, rename = "emulator")]
+    profiles: Vec<EmulatorProfile>,
+}
+
+/// ## Emulator Registry
+/// Set of emulator profiles, stored by default on
+/// `$XDG_CONFIG_HOME/starframe/emulators.toml`. It decides which profile
+/// runs each library entry: a profile listing the game's platform wins, then
+/// the first profile accepting the file extension. Games without a profile
+/// are treated as native executables.
+pub struct EmulatorRegistry {
+    path: PathBuf,
+    profiles: Vec<EmulatorProfile>,
+}
+
+impl EmulatorRegistry {
+    pub fn new(path: PathBuf) -> EmulatorRegistry {
+        EmulatorRegistry {
+            path,
+            profiles: vec![],
+        }
+    }
+
+    pub fn default_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("starframe")
+            .join("emulators.toml")
+    }
+
+    pub fn open_default() -> Result<EmulatorRegistry, EmulatorError> {
+        EmulatorRegistry::open(EmulatorRegistry::default_path())
+    }
+
+    /// Reads profiles from `path`. Profiles aren't validated here, so a missing
+    /// emulator only fails when a game tries to use it.
+    pub fn open(path: PathBuf) -> Result<EmulatorRegistry, EmulatorError> {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(EmulatorRegistry::new(path)),
+            Err(err) => return Err(err.into()),
+        };
+
+        let file: EmulatorsFile = toml::from_str(&content)?;
+        if file.version > EMULATORS_VERSION {
+            return Err(EmulatorError::UnsupportedVersion(file.version));
+        }
+        Ok(EmulatorRegistry {
+            path,
+            profiles: file.profiles,
+        })
+    }
+
+    pub fn save(&self) -> Result<(), EmulatorError> {
+        let file = EmulatorsFile {
+            version: EMULATORS_VERSION,
+            profiles: self.profiles.clone(),
+        };
+        let content = toml::to_string(&file)?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp_path = self.path.with_extension("toml.tmp");
+        fs::write(&temp_path, content)?;
+        fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Validates and registers a new profile. Names must be unique.
+    pub fn add(&mut self, profile: EmulatorProfile) -> Result<(), EmulatorError> {
+        if self.get(&profile.name).is_some() {
+            return Err(EmulatorError::DuplicatedProfile(profile.name));
+        }
+        profile.validate()?;
+        self.profiles.push(profile);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<EmulatorProfile> {
+        let index = self.profiles.iter().position(|p| p.name == name)?;
+        Some(self.profiles.remove(index))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&EmulatorProfile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    pub fn profiles(&self) -> &[EmulatorProfile] {
+        &self.profiles
+    }
+
+    /// Picks the profile that should run `game`, if any.
+    pub fn resolve(&self, game: &GameEntry) -> Option<&EmulatorProfile> {
+        self.profiles.iter().find(|p| p.supports_platform(&game.platform))
+            .or_else(|| self.profiles.iter().find(|p| p.supports_file(&game.path)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(arguments: &str) -> EmulatorProfile {
+        EmulatorProfile::new("Stub", PathBuf::from("true"), arguments)
+    }
+
+    fn text(text: &str) -> Piece {
+        Piece::Text(String::from(text))
+    }
+
+    fn placeholder(name: &str) -> Piece {
+        Piece::Placeholder(String::from(name))
+    }
+
+    #[test]
+    fn quotes_keep_spaces_in_one_word() {
+        let words = profile(r#"--config "/etc/my emu/a b.cfg" '{rom}' --save={rom_dir}/'save files'"#).template_words().unwrap();
+        assert_eq!(words, vec![
+            vec![text("--config")],
+            vec![text("/etc/my emu/a b.cfg")],
+            vec![placeholder("rom")],
+            vec![text("--save="), placeholder("rom_dir"), text("/save files")],
+        ]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn builds_arguments_from_the_game() {
+        let game = GameEntry::new("Paper Mario", "n64", PathBuf::from("/roms/Paper Mario.z64"));
+        let command = profile(r#"--config "/etc/my emu/a b.cfg" --title {title} {rom}"#).build_command(&game).unwrap();
+        let arguments: Vec<_> = command.get_args().map(|a| a.to_string_lossy().into_owned()).collect();
+        assert_eq!(arguments, ["--config", "/etc/my emu/a b.cfg", "--title", "Paper Mario", "/roms/Paper Mario.z64"]);
+        assert_eq!(command.get_current_dir(), Some(Path::new("/roms")));
+    }
+
+    #[test]
+    fn rejects_unknown_placeholders() {
+        match profile("{rom} --region {region}").validate() {
+            Err(EmulatorError::UnknownPlaceholder { placeholder, .. }) => assert_eq!(placeholder, "region"),
+            other => panic!("validated as {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_unclosed_braces_and_quotes() {
+        for (arguments, expected) in [("--fullscreen {rom", "unclosed '{'"), (r#""{rom}"#, "unclosed quote"), ("{rom}}", "unexpected '}'")] {
+            match profile(arguments).validate() {
+                Err(EmulatorError::MalformedTemplate { reason, .. }) => assert_eq!(reason, expected),
+                other => panic!("{} validated as {:?}", arguments, other),
+            }
+        }
+    }
+
+    #[test]
+    fn requires_the_rom_placeholder() {
+        match profile("--fullscreen {rom_dir}").validate() {
+            Err(EmulatorError::MissingPlaceholder { placeholder, .. }) => assert_eq!(placeholder, "rom"),
+            other => panic!("validated as {:?}", other),
+        }
+    }
+
+    #[test]
+    fn platform_match_wins_over_extension() {
+        let mut by_extension = profile("{rom}");
+        by_extension.name = String::from("By extension");
+        by_extension.extensions = vec![String::from(".Z64")];
+        let mut by_platform = profile("{rom}");
+        by_platform.name = String::from("By platform");
+        by_platform.platforms = vec![String::from("N64")];
+        let registry = EmulatorRegistry {
+            path: PathBuf::from("emulators.toml"),
+            profiles: vec![by_extension, by_platform],
+        };
+
+        let game = GameEntry::new("Paper Mario", "n64", PathBuf::from("paper_mario.z64"));
+        assert_eq!(registry.resolve(&game).map(|p| p.name.as_str()), Some("By platform"));
+        let game = GameEntry::new("Paper Mario", "unknown", PathBuf::from("paper_mario.z64"));
+        assert_eq!(registry.resolve(&game).map(|p| p.name.as_str()), Some("By extension"));
+        let game = GameEntry::new("Tux Racer", "pc", PathBuf::from("tuxracer"));
+        assert!(registry.resolve(&game).is_none());
+    }
+}
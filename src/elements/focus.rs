@@ -0,0 +1,121 @@
+use crate::elements::input::Action;
+use raylib::prelude::*;
+
+/// ## Focus Group
+/// Keeps which widget of a scene is focused when navigating without mouse.
+/// Widgets are referred by their index on the scene, and each one has an area
+/// on screen that the scene keeps up to date with `set_areas`.
+///
+/// Pressing a direction follows an explicit link if there is one:
+///
+/// ```rust
+/// // Pressing Down on widget 1 always goes to widget 4.
+/// focus.link(1, Action::Down, 4);
+/// ```
+///
+/// Otherwise it goes to the nearest widget on that direction, favouring the
+/// ones aligned with the focused widget. Nothing is focused until the first
+/// direction is pressed, so mouse users don't see a focus they didn't ask for.
+pub struct FocusGroup {
+    focused: Option<usize>,
+    areas: Vec<Rectangle>,
+    links: Vec<(usize, Action, usize)>,
+}
+
+impl FocusGroup {
+    pub fn new() -> FocusGroup {
+        FocusGroup {
+            focused: None,
+            areas: vec![],
+            links: vec![],
+        }
+    }
+
+    /// Sets where each widget is, by index. Call it after laying out widgets.
+    pub fn set_areas(&mut self, areas: Vec<Rectangle>) {
+        self.areas = areas;
+        if self.focused.is_some_and(|index| index >= self.areas.len()) {
+            self.focused = None;
+        }
+    }
+
+    /// Makes `direction` on widget `from` always go to widget `to`.
+    pub fn link(&mut self, from: usize, direction: Action, to: usize) -> &mut FocusGroup {
+        self.links.retain(|(f, d, _)| !(*f == from && *d == direction));
+        self.links.push((from, direction, to));
+        self
+    }
+
+    pub fn focused(&self) -> Option<usize> {
+        self.focused
+    }
+
+    pub fn focus(&mut self, index: usize) {
+        if index < self.areas.len() {
+            self.focused = Some(index);
+        }
+    }
+
+    /// Drops the focus, usually because the mouse was moved.
+    pub fn clear(&mut self) {
+        self.focused = None;
+    }
+
+    /// Moves focus following `direction`. Returns `true` if the focus changed.
+    pub fn navigate(&mut self, direction: Action) -> bool {
+        if !direction.is_direction() || self.areas.is_empty() {
+            return false;
+        }
+
+        let from = match self.focused {
+            Some(from) => from,
+            None => {
+                self.focused = Some(0);
+                return true;
+            }
+        };
+
+        let linked = self.links.iter().find(|(f, d, _)| *f == from && *d == direction).map(|(_, _, to)| *to);
+        let next = linked.filter(|to| *to < self.areas.len()).or_else(|| self.nearest(from, direction));
+        match next {
+            Some(next) if next != from => {
+                self.focused = Some(next);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Nearest widget towards `direction`. Distance across the direction counts
+    /// twice, so widgets on the same row (or column) win over diagonal ones.
+    fn nearest(&self, from: usize, direction: Action) -> Option<usize> {
+        let origin = center(&self.areas[from]);
+        self.areas.iter().enumerate()
+            .filter(|(index, _)| *index != from)
+            .filter_map(|(index, area)| {
+                let target = center(area);
+                let (along, across) = match direction {
+                    Action::Up => (origin.y - target.y, target.x - origin.x),
+                    Action::Down => (target.y - origin.y, target.x - origin.x),
+                    Action::Left => (origin.x - target.x, target.y - origin.y),
+                    _ => (target.x - origin.x, target.y - origin.y),
+                };
+                if along <= 0.0 {
+                    return None;
+                }
+                Some((index, along + across.abs() * 2.0))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+    }
+}
+
+impl Default for FocusGroup {
+    fn default() -> Self {
+        FocusGroup::new()
+    }
+}
+
+fn center(area: &Rectangle) -> Vector2 {
+    Vector2::new(area.x + area.width / 2.0, area.y + area.height / 2.0)
+}
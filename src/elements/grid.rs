@@ -0,0 +1,192 @@
+use crate::elements::{Action, AssetCache, CoverBook};
+use raylib::prelude::*;
+
+/// Height of a thumbnail relative to its width, like portrait box art.
+const THUMBNAIL_RATIO: f32 = 4.0 / 3.0;
+/// Space under each thumbnail for the game's title.
+const LABEL_HEIGHT: f32 = 24.0;
+const LABEL_SIZE: i32 = 10;
+/// How fast scrolling catches up with its target, per second. Higher is snappier.
+const SCROLL_SPEED: f32 = 12.0;
+
+/// ## Cover Grid
+/// Shows the covers of a `CoverBook` as rows of thumbnails, sharing the book's
+/// selection, so switching between the grid and the single cover view keeps
+/// the selected game:
+///
+/// ```rust
+/// let mut grid = CoverGrid::new(4);
+/// grid.navigate(&mut book, Action::Down);
+/// grid.update(&book, area, dt);
+/// grid.draw(d, &book, assets, area);
+/// ```
+///
+/// Only rows inside `area` are drawn, so libraries of any size cost the same.
+/// Scrolling eases towards its target, which follows the selected cover.
+pub struct CoverGrid {
+    columns: usize,
+    /// Space between cells and around the grid, in pixels.
+    pub spacing: f32,
+    scroll: f32,
+    target_scroll: f32,
+    /// Selection on last update, to scroll only when it changes.
+    followed: Option<usize>,
+}
+
+impl CoverGrid {
+    pub fn new(columns: usize) -> CoverGrid {
+        CoverGrid {
+            columns: columns.max(1),
+            spacing: 12.0,
+            scroll: 0.0,
+            target_scroll: 0.0,
+            followed: None,
+        }
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn set_columns(&mut self, columns: usize) {
+        self.columns = columns.max(1);
+        self.followed = None;
+    }
+
+    /// Size of a cell, thumbnail and label, when the grid fills `area`'s width.
+    fn cell_size(&self, area: Rectangle) -> Vector2 {
+        let columns = self.columns as f32;
+        let width = ((area.width - self.spacing * (columns + 1.0)) / columns).max(1.0);
+        Vector2::new(width, width * THUMBNAIL_RATIO + LABEL_HEIGHT)
+    }
+
+    fn row_height(&self, area: Rectangle) -> f32 {
+        self.cell_size(area).y + self.spacing
+    }
+
+    fn rows(&self, book: &CoverBook) -> usize {
+        book.covers.len().div_ceil(self.columns)
+    }
+
+    fn max_scroll(&self, book: &CoverBook, area: Rectangle) -> f32 {
+        (self.rows(book) as f32 * self.row_height(area) + self.spacing - area.height).max(0.0)
+    }
+
+    /// Where the cell of cover `index` is on screen, scrolled.
+    pub fn cell_bounds(&self, index: usize, area: Rectangle) -> Rectangle {
+        let size = self.cell_size(area);
+        let (row, column) = (index / self.columns, index % self.columns);
+        Rectangle::new(
+            area.x + self.spacing + column as f32 * (size.x + self.spacing),
+            area.y + self.spacing + row as f32 * self.row_height(area) - self.scroll,
+            size.x,
+            size.y
+        )
+    }
+
+    /// Cover under `point`, if any.
+    pub fn index_at(&self, book: &CoverBook, area: Rectangle, point: Vector2) -> Option<usize> {
+        if !area.check_collision_point_rec(point) {
+            return None;
+        }
+        (0..book.covers.len()).find(|index| self.cell_bounds(*index, area).check_collision_point_rec(point))
+    }
+
+    /// Moves the selection of `book` following `direction`. Returns `false`
+    /// when it would leave the grid, like pressing Up on the first row, so the
+    /// scene can move focus elsewhere.
+    pub fn navigate(&mut self, book: &mut CoverBook, direction: Action) -> bool {
+        let (selected, count) = (book.selected(), book.covers.len());
+        let target = match direction {
+            Action::Left if selected > 0 => selected - 1,
+            Action::Right if selected + 1 < count => selected + 1,
+            Action::Up if selected >= self.columns => selected - self.columns,
+            Action::Down if selected + self.columns < count => selected + self.columns,
+            // From the row above a shorter last row, to its last cover.
+            Action::Down if selected / self.columns + 1 < self.rows(book) => count - 1,
+            _ => return false,
+        };
+        book.select(target);
+        true
+    }
+
+    /// Moves the selection by `pages` screens of rows, backwards when negative.
+    pub fn page(&mut self, book: &mut CoverBook, area: Rectangle, pages: i32) {
+        if book.covers.is_empty() {
+            return;
+        }
+        let visible_rows = ((area.height / self.row_height(area)) as i64).max(1);
+        let target = book.selected() as i64 + pages as i64 * visible_rows * self.columns as i64;
+        book.select(target.clamp(0, book.covers.len() as i64 - 1) as usize);
+    }
+
+    /// Scrolls by `pixels`, down when positive, without moving the selection.
+    pub fn scroll_by(&mut self, pixels: f32) {
+        self.target_scroll += pixels;
+    }
+
+    /// Scrolls to the selected cover if it changed, and eases the scroll
+    /// towards its target by `dt` seconds.
+    pub fn update(&mut self, book: &CoverBook, area: Rectangle, dt: f32) {
+        let selected = book.selected();
+        if self.followed != Some(selected) && !book.covers.is_empty() {
+            self.followed = Some(selected);
+            let row_top = (selected / self.columns) as f32 * self.row_height(area);
+            let row_bottom = row_top + self.row_height(area) + self.spacing;
+            if row_top < self.target_scroll {
+                self.target_scroll = row_top;
+            } else if row_bottom > self.target_scroll + area.height {
+                self.target_scroll = row_bottom - area.height;
+            }
+        }
+
+        self.target_scroll = self.target_scroll.clamp(0.0, self.max_scroll(book, area));
+        self.scroll += (self.target_scroll - self.scroll) * (1.0 - (-SCROLL_SPEED * dt).exp());
+        if (self.target_scroll - self.scroll).abs() < 0.5 {
+            self.scroll = self.target_scroll;
+        }
+    }
+
+    /// Draws the rows of thumbnails visible on `area`, with the selected one
+    /// outlined.
+    pub fn draw(&self, d: &mut RaylibDrawHandle, book: &CoverBook, assets: &AssetCache, area: Rectangle) {
+        let row_height = self.row_height(area);
+        let first_row = (self.scroll / row_height).floor() as usize;
+        let last_row = (((self.scroll + area.height) / row_height).ceil() as usize).min(self.rows(book));
+        let visible = (first_row * self.columns)..(last_row * self.columns).min(book.covers.len());
+
+        let mut d = d.begin_scissor_mode(area.x as i32, area.y as i32, area.width as i32, area.height as i32);
+        for index in visible {
+            let cover = &book.covers[index];
+            let cell = self.cell_bounds(index, area);
+            let thumbnail = Rectangle::new(cell.x, cell.y, cell.width, cell.height - LABEL_HEIGHT);
+
+            d.draw_rectangle_rec(thumbnail, Color::LIGHTGRAY);
+            cover.draw_into(&mut d, assets, thumbnail);
+            if index == book.selected() {
+                d.draw_rectangle_lines_ex(thumbnail, 3, Color::BLUE);
+            }
+
+            let label = fit_label(&cover.label, LABEL_SIZE, cell.width as i32);
+            d.draw_text(
+                &label,
+                (cell.x + cell.width / 2.0) as i32 - measure_text(&label, LABEL_SIZE) / 2,
+                (cell.y + cell.height - LABEL_HEIGHT) as i32 + 7,
+                LABEL_SIZE,
+                Color::DARKGRAY
+            );
+        }
+    }
+}
+
+/// Cuts `text` to fit `width` pixels, ending it with "..." when cut.
+fn fit_label(text: &str, font_size: i32, width: i32) -> String {
+    if measure_text(text, font_size) <= width {
+        return text.to_string();
+    }
+    let mut label: String = text.to_string();
+    while !label.is_empty() && measure_text(&format!("{}...", label), font_size) > width {
+        label.pop();
+    }
+    format!("{}...", label.trim_end())
+}
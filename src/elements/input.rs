@@ -0,0 +1,343 @@
+use raylib::prelude::*;
+use std::fmt;
+
+/// Seconds a direction must be held before it starts repeating.
+const REPEAT_DELAY: f64 = 0.4;
+/// Seconds between repeats of a held direction.
+const REPEAT_INTERVAL: f64 = 0.12;
+
+/// Logical actions scenes react to, regardless of the device that produced them.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
+pub enum Action {
+    Up = 0,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Back,
+    Menu,
+    PageLeft,
+    PageRight,
+}
+
+impl Action {
+    pub const ALL: [Action; 9] = [
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Confirm,
+        Action::Back,
+        Action::Menu,
+        Action::PageLeft,
+        Action::PageRight,
+    ];
+
+    /// Directions repeat while held, like keys on a text field.
+    pub fn is_direction(&self) -> bool {
+        matches!(self, Action::Up | Action::Down | Action::Left | Action::Right)
+    }
+
+    /// Paging repeats while held too, but doesn't move focus.
+    pub fn repeats(&self) -> bool {
+        self.is_direction() || matches!(self, Action::PageLeft | Action::PageRight)
+    }
+
+    /// Name of the action on the config file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Confirm => "confirm",
+            Action::Back => "back",
+            Action::Menu => "menu",
+            Action::PageLeft => "page_left",
+            Action::PageRight => "page_right",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+/// A physical input that can trigger an `Action`.
+#[derive(Debug, Copy, Clone)]
+pub enum Binding {
+    Key(KeyboardKey),
+    GamepadButton(GamepadButton),
+    /// A gamepad axis pushed past the dead zone, towards the sign of the `f32`.
+    GamepadAxis(GamepadAxis, f32),
+    Mouse(MouseButton),
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Raylib names without their prefix, like "ENTER" for `KEY_ENTER`.
+        match self {
+            Binding::Key(key) => write!(f, "{}", format!("{:?}", key).trim_start_matches("KEY_")),
+            Binding::GamepadButton(button) => {
+                write!(f, "Pad {}", format!("{:?}", button).trim_start_matches("GAMEPAD_BUTTON_"))
+            }
+            Binding::GamepadAxis(axis, sign) => write!(
+                f, "Pad {}{}",
+                format!("{:?}", axis).trim_start_matches("GAMEPAD_AXIS_"),
+                if *sign < 0.0 { "-" } else { "+" }
+            ),
+            Binding::Mouse(button) => write!(f, "{}", format!("{:?}", button).trim_end_matches("_BUTTON")),
+        }
+    }
+}
+
+/// ## Input Map
+/// Relates every `Action` to the bindings that trigger it.
+#[derive(Debug, Clone)]
+pub struct InputMap {
+    bindings: Vec<(Action, Binding)>,
+    /// Gamepad read by the map.
+    pub gamepad: GamepadNumber,
+    /// How far an axis must be pushed to count as pressed, from 0.0 to 1.0.
+    pub dead_zone: f32,
+}
+
+impl InputMap {
+    /// Creates a map without any binding.
+    pub fn new() -> InputMap {
+        InputMap {
+            bindings: vec![],
+            gamepad: GamepadNumber::GAMEPAD_PLAYER1,
+            dead_zone: 0.5,
+        }
+    }
+
+    /// Arrows/WASD, Enter/Space, Backspace and Q/E or Page Up/Down on keyboard;
+    /// D-pad, left stick, face buttons, Start and shoulders on gamepad; right
+    /// and middle clicks on mouse.
+    pub fn default_bindings() -> InputMap {
+        use GamepadAxis::*;
+        use GamepadButton::*;
+        use KeyboardKey::*;
+
+        let mut map = InputMap::new();
+        map.bind(Action::Up, Binding::Key(KEY_UP))
+            .bind(Action::Up, Binding::Key(KEY_W))
+            .bind(Action::Up, Binding::GamepadButton(GAMEPAD_BUTTON_LEFT_FACE_UP))
+            .bind(Action::Up, Binding::GamepadAxis(GAMEPAD_AXIS_LEFT_Y, -1.0))
+            .bind(Action::Down, Binding::Key(KEY_DOWN))
+            .bind(Action::Down, Binding::Key(KEY_S))
+            .bind(Action::Down, Binding::GamepadButton(GAMEPAD_BUTTON_LEFT_FACE_DOWN))
+            .bind(Action::Down, Binding::GamepadAxis(GAMEPAD_AXIS_LEFT_Y, 1.0))
+            .bind(Action::Left, Binding::Key(KEY_LEFT))
+            .bind(Action::Left, Binding::Key(KEY_A))
+            .bind(Action::Left, Binding::GamepadButton(GAMEPAD_BUTTON_LEFT_FACE_LEFT))
+            .bind(Action::Left, Binding::GamepadAxis(GAMEPAD_AXIS_LEFT_X, -1.0))
+            .bind(Action::Right, Binding::Key(KEY_RIGHT))
+            .bind(Action::Right, Binding::Key(KEY_D))
+            .bind(Action::Right, Binding::GamepadButton(GAMEPAD_BUTTON_LEFT_FACE_RIGHT))
+            .bind(Action::Right, Binding::GamepadAxis(GAMEPAD_AXIS_LEFT_X, 1.0))
+            .bind(Action::Confirm, Binding::Key(KEY_ENTER))
+            .bind(Action::Confirm, Binding::Key(KEY_SPACE))
+            .bind(Action::Confirm, Binding::GamepadButton(GAMEPAD_BUTTON_RIGHT_FACE_DOWN))
+            .bind(Action::Back, Binding::Key(KEY_BACKSPACE))
+            .bind(Action::Back, Binding::GamepadButton(GAMEPAD_BUTTON_RIGHT_FACE_RIGHT))
+            .bind(Action::Back, Binding::Mouse(MouseButton::MOUSE_RIGHT_BUTTON))
+            .bind(Action::Menu, Binding::Key(KEY_TAB))
+            .bind(Action::Menu, Binding::GamepadButton(GAMEPAD_BUTTON_MIDDLE_RIGHT))
+            .bind(Action::Menu, Binding::Mouse(MouseButton::MOUSE_MIDDLE_BUTTON))
+            .bind(Action::PageLeft, Binding::Key(KEY_Q))
+            .bind(Action::PageLeft, Binding::Key(KEY_PAGE_UP))
+            .bind(Action::PageLeft, Binding::GamepadButton(GAMEPAD_BUTTON_LEFT_TRIGGER_1))
+            .bind(Action::PageRight, Binding::Key(KEY_E))
+            .bind(Action::PageRight, Binding::Key(KEY_PAGE_DOWN))
+            .bind(Action::PageRight, Binding::GamepadButton(GAMEPAD_BUTTON_RIGHT_TRIGGER_1));
+        map
+    }
+
+    pub fn bind(&mut self, action: Action, binding: Binding) -> &mut InputMap {
+        self.bindings.push((action, binding));
+        self
+    }
+
+    /// Removes the keyboard bindings of `action`, keeping gamepad and mouse ones.
+    pub fn clear_keys(&mut self, action: Action) -> &mut InputMap {
+        self.bindings.retain(|(a, b)| *a != action || !matches!(b, Binding::Key(_)));
+        self
+    }
+
+    pub fn bindings_of(&self, action: Action) -> impl Iterator<Item = &Binding> {
+        self.bindings.iter().filter(move |(a, _)| *a == action).map(|(_, b)| b)
+    }
+
+    fn is_down(&self, rl: &RaylibHandle, binding: &Binding) -> bool {
+        match *binding {
+            Binding::Key(key) => rl.is_key_down(key),
+            Binding::GamepadButton(button) => {
+                rl.is_gamepad_available(self.gamepad) && rl.is_gamepad_button_down(self.gamepad, button)
+            }
+            Binding::GamepadAxis(axis, direction) => {
+                rl.is_gamepad_available(self.gamepad)
+                    && rl.get_gamepad_axis_movement(self.gamepad, axis) * direction.signum() > self.dead_zone
+            }
+            Binding::Mouse(button) => rl.is_mouse_button_down(button),
+        }
+    }
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        InputMap::default_bindings()
+    }
+}
+
+/// Actions of a single frame.
+#[derive(Debug, Clone, Default)]
+pub struct InputState {
+    pressed: Vec<Action>,
+    held: Vec<Action>,
+    pub mouse_position: Vector2,
+    /// `true` when the mouse moved since last frame. Scenes use it to give
+    /// focus back to the mouse.
+    pub mouse_moved: bool,
+    /// Mouse wheel movement on this frame, positive when scrolled up.
+    pub wheel: f32,
+    /// Left mouse button went down on this frame.
+    pub mouse_pressed: bool,
+    /// Left mouse button is held.
+    pub mouse_down: bool,
+    /// Keyboard keys pressed on this frame, in order. Unlike actions they
+    /// aren't mapped, for text fields and rebinding.
+    pub keys: Vec<KeyboardKey>,
+    /// Text typed on this frame, without control characters.
+    pub typed: String,
+}
+
+impl InputState {
+    /// `true` on the frame `action` was triggered, and on repeats of held directions.
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    /// `true` while `action` is held.
+    pub fn held(&self, action: Action) -> bool {
+        self.held.contains(&action)
+    }
+
+    /// Same state with nothing pressed on this frame, keeping what's held.
+    pub fn without_presses(&self) -> InputState {
+        InputState {
+            pressed: vec![],
+            held: self.held.clone(),
+            mouse_position: self.mouse_position,
+            mouse_moved: false,
+            wheel: 0.0,
+            mouse_pressed: false,
+            mouse_down: self.mouse_down,
+            keys: vec![],
+            typed: String::new(),
+        }
+    }
+
+    /// Adds a later poll to this state, as if both frames were one: presses of
+    /// both are kept, while held actions and positions are the later ones.
+    pub fn merge(&mut self, later: InputState) {
+        for action in later.pressed {
+            if !self.pressed.contains(&action) {
+                self.pressed.push(action);
+            }
+        }
+        self.held = later.held;
+        self.mouse_position = later.mouse_position;
+        self.mouse_moved |= later.mouse_moved;
+        self.wheel += later.wheel;
+        self.mouse_pressed |= later.mouse_pressed;
+        self.mouse_down = later.mouse_down;
+        self.keys.extend(later.keys);
+        self.typed.push_str(&later.typed);
+    }
+
+    /// First direction pressed on this frame, if any.
+    pub fn direction(&self) -> Option<Action> {
+        self.pressed.iter().copied().find(Action::is_direction)
+    }
+}
+
+/// ## Input
+/// Reads devices through an `InputMap` once per frame, turning them in an
+/// `InputState`. It should be polled exactly once per frame, since presses are
+/// found by comparing with the previous poll.
+pub struct Input {
+    /// When each held action started being held, indexed by `Action as usize`.
+    held_since: [Option<f64>; Action::ALL.len()],
+    last_repeat: [f64; Action::ALL.len()],
+    last_mouse: Vector2,
+}
+
+impl Input {
+    pub fn new() -> Input {
+        Input {
+            held_since: [None; Action::ALL.len()],
+            last_repeat: [0.0; Action::ALL.len()],
+            last_mouse: Vector2::new(0.0, 0.0),
+        }
+    }
+
+    pub fn poll(&mut self, rl: &mut RaylibHandle, map: &InputMap) -> InputState {
+        let now = rl.get_time();
+        let mut state = InputState::default();
+
+        for action in Action::ALL {
+            let index = action as usize;
+            let down = map.bindings_of(action).any(|binding| map.is_down(rl, binding));
+            if !down {
+                self.held_since[index] = None;
+                continue;
+            }
+
+            state.held.push(action);
+            match self.held_since[index] {
+                None => {
+                    self.held_since[index] = Some(now);
+                    self.last_repeat[index] = now;
+                    state.pressed.push(action);
+                }
+                Some(since) => {
+                    let repeating = action.repeats()
+                        && now - since >= REPEAT_DELAY
+                        && now - self.last_repeat[index] >= REPEAT_INTERVAL;
+                    if repeating {
+                        self.last_repeat[index] = now;
+                        state.pressed.push(action);
+                    }
+                }
+            }
+        }
+
+        state.mouse_position = rl.get_mouse_position();
+        state.mouse_moved = state.mouse_position != self.last_mouse;
+        state.wheel = rl.get_mouse_wheel_move();
+        state.mouse_pressed = rl.is_mouse_button_pressed(MouseButton::MOUSE_LEFT_BUTTON);
+        state.mouse_down = rl.is_mouse_button_down(MouseButton::MOUSE_LEFT_BUTTON);
+        self.last_mouse = state.mouse_position;
+        state.keys = std::iter::from_fn(|| rl.get_key_pressed()).collect();
+        state.typed = std::iter::from_fn(|| char_pressed(rl)).filter(|c| !c.is_control()).collect();
+        state
+    }
+}
+
+/// Next character on raylib's queue of typed text, which has no safe binding.
+/// Taking the handle makes sure the window is open.
+fn char_pressed(_rl: &RaylibHandle) -> Option<char> {
+    let code = unsafe { raylib::ffi::GetCharPressed() };
+    if code <= 0 {
+        return None;
+    }
+    char::from_u32(code as u32)
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Input::new()
+    }
+}
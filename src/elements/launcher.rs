@@ -0,0 +1,253 @@
+use crate::elements::{EmulatorError, EmulatorRegistry, GameEntry};
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub enum LaunchError {
+    AlreadyRunning,
+    MissingExecutable(PathBuf),
+    Emulator(EmulatorError),
+    Spawn(io::Error),
+}
+
+impl fmt::Display for LaunchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LaunchError::AlreadyRunning => write!(f, "another game is already running"),
+            LaunchError::MissingExecutable(path) => write!(f, "{} doesn't exist", path.display()),
+            LaunchError::Emulator(err) => write!(f, "{}", err),
+            LaunchError::Spawn(err) => write!(f, "can't start game: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for LaunchError {}
+
+/// What happened to a game after it was closed.
+#[derive(Debug, Clone)]
+pub struct ExitReport {
+    pub game_id: u64,
+    pub title: String,
+    /// Exit code of the process. It's `None` when the process was killed by a signal.
+    pub code: Option<i32>,
+    pub play_time: Duration,
+}
+
+impl fmt::Display for ExitReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let minutes = self.play_time.as_secs() / 60;
+        let seconds = self.play_time.as_secs() % 60;
+        match self.code {
+            Some(0) => write!(f, "{} closed after {}m{:02}s", self.title, minutes, seconds),
+            Some(code) => write!(f, "{} exited with code {} after {}m{:02}s", self.title, code, minutes, seconds),
+            None => write!(f, "{} was terminated after {}m{:02}s", self.title, minutes, seconds),
+        }
+    }
+}
+
+struct RunningGame {
+    game_id: u64,
+    title: String,
+    child: Child,
+    started: Instant,
+}
+
+/// ## Launcher
+/// Runs one game at a time as a child process and keeps track of it.
+/// The launcher never blocks: call `poll` once per frame and it will hand an
+/// `ExitReport` back when the game finishes.
+///
+/// ```rust
+/// let mut launcher = Launcher::new();
+/// launcher.launch_game(&game, &emulators)?;
+///
+/// // every frame...
+/// if let Some(report) = launcher.poll() {
+///     println!("{}", report);
+/// }
+/// ```
+pub struct Launcher {
+    running: Option<RunningGame>,
+}
+
+impl Default for Launcher {
+    fn default() -> Self {
+        Launcher::new()
+    }
+}
+
+impl Launcher {
+    pub fn new() -> Launcher {
+        Launcher { running: None }
+    }
+
+    /// Runs `game` with the emulator profile resolved by `emulators`, or as a
+    /// native executable when no profile matches it.
+    pub fn launch_game(&mut self, game: &GameEntry, emulators: &EmulatorRegistry) -> Result<(), LaunchError> {
+        match emulators.resolve(game) {
+            Some(profile) => {
+                let command = profile.build_command(game).map_err(LaunchError::Emulator)?;
+                self.launch(game, command)
+            }
+            None => self.launch_native(game),
+        }
+    }
+
+    /// Runs `game.path` directly as a native executable, using its folder as
+    /// working directory.
+    pub fn launch_native(&mut self, game: &GameEntry) -> Result<(), LaunchError> {
+        if !game.path.is_file() {
+            return Err(LaunchError::MissingExecutable(game.path.clone()));
+        }
+
+        let mut command = Command::new(&game.path);
+        if let Some(folder) = game.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            command.current_dir(folder);
+        }
+        self.launch(game, command)
+    }
+
+    /// Spawns an already built `command` as the process of `game`.
+    pub fn launch(&mut self, game: &GameEntry, mut command: Command) -> Result<(), LaunchError> {
+        if self.running.is_some() {
+            return Err(LaunchError::AlreadyRunning);
+        }
+
+        let child = command.spawn().map_err(LaunchError::Spawn)?;
+        self.running = Some(RunningGame {
+            game_id: game.id,
+            title: game.title.clone(),
+            child,
+            started: Instant::now(),
+        });
+        Ok(())
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Title of the running game, if any.
+    pub fn running_title(&self) -> Option<&str> {
+        self.running.as_ref().map(|r| r.title.as_str())
+    }
+
+    /// Checks if the running game has finished, without blocking.
+    pub fn poll(&mut self) -> Option<ExitReport> {
+        let status = match self.running.as_mut()?.child.try_wait() {
+            Ok(Some(status)) => Some(status),
+            Ok(None) => return None,
+            Err(err) => {
+                println!("lost track of game process: {}", err);
+                None
+            }
+        };
+        self.running.take().map(|running| running.report(status))
+    }
+
+    /// Kills the running game and waits for it.
+    pub fn kill(&mut self) -> Option<ExitReport> {
+        let mut running = self.running.take()?;
+        let _ = running.child.kill();
+        let status = running.child.wait().ok();
+        Some(running.report(status))
+    }
+}
+
+impl RunningGame {
+    fn report(self, status: Option<ExitStatus>) -> ExitReport {
+        ExitReport {
+            game_id: self.game_id,
+            title: self.title,
+            code: status.and_then(|s| s.code()),
+            play_time: self.started.elapsed(),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+    use std::thread;
+
+    /// Writes an executable shell script standing in for a game.
+    fn stub_game(name: &str, script: &str) -> GameEntry {
+        let dir = std::env::temp_dir().join(format!("starframe-launcher-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("game.sh");
+        fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut game = GameEntry::new(name, "PC", path);
+        game.id = 7;
+        game
+    }
+
+    fn wait_for_exit(launcher: &mut Launcher) -> ExitReport {
+        for _ in 0..500 {
+            if let Some(report) = launcher.poll() {
+                return report;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("stub game never exited");
+    }
+
+    fn remove_stub(game: &GameEntry) {
+        let _ = fs::remove_dir_all(game.path.parent().unwrap());
+    }
+
+    #[test]
+    fn reports_exit_code_of_stub_game() {
+        let game = stub_game("exit-code", "sleep 0.1\nexit 3");
+        let mut launcher = Launcher::new();
+        launcher.launch_native(&game).unwrap();
+        assert!(launcher.is_running());
+        assert_eq!(launcher.running_title(), Some("exit-code"));
+
+        let report = wait_for_exit(&mut launcher);
+        assert_eq!(report.game_id, 7);
+        assert_eq!(report.code, Some(3));
+        assert!(report.play_time >= Duration::from_millis(100));
+        assert!(!launcher.is_running());
+        assert!(launcher.poll().is_none());
+        remove_stub(&game);
+    }
+
+    #[test]
+    fn runs_one_game_at_a_time() {
+        let game = stub_game("one-at-a-time", "exit 0");
+        let mut launcher = Launcher::new();
+        launcher.launch_native(&game).unwrap();
+        assert!(matches!(launcher.launch_native(&game), Err(LaunchError::AlreadyRunning)));
+
+        assert_eq!(wait_for_exit(&mut launcher).code, Some(0));
+        remove_stub(&game);
+    }
+
+    #[test]
+    fn kill_reports_terminated_game() {
+        let game = stub_game("killed", "exec sleep 30");
+        let mut launcher = Launcher::new();
+        launcher.launch_native(&game).unwrap();
+
+        let report = launcher.kill().unwrap();
+        assert_eq!(report.code, None);
+        assert!(!launcher.is_running());
+        remove_stub(&game);
+    }
+
+    #[test]
+    fn missing_executable_isnt_spawned() {
+        let game = GameEntry::new("missing", "PC", Path::new("/nonexistent/starframe/game").to_path_buf());
+        let mut launcher = Launcher::new();
+        assert!(matches!(launcher.launch_native(&game), Err(LaunchError::MissingExecutable(_))));
+        assert!(!launcher.is_running());
+    }
+}
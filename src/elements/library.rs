@@ -0,0 +1,446 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Version of the library file layout. Bump it when `GameEntry` changes in a way
+/// that old files can't be read with `#[serde(default)]` alone.
+pub const LIBRARY_VERSION: u32 = 1;
+
+/// ## Game Entry
+/// A single game registered on the library. `path` points to the native executable
+/// or to the ROM file that will be handed to an emulator.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GameEntry {
+    pub id: u64,
+    pub title: String,
+    pub platform: String,
+    pub path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cover: Option<PathBuf>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Seconds since UNIX epoch when the game was added.
+    #[serde(default)]
+    pub added: u64,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(default)]
+    pub favorite: bool,
+    /// Seconds played, summing every session.
+    #[serde(default)]
+    pub play_time: u64,
+    /// Seconds since UNIX epoch when the last session ended.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_played: Option<u64>,
+}
+
+impl GameEntry {
+    /// Creates an entry that is not registered yet. `id` and `added` are
+    /// filled by `GameLibrary::add`.
+    pub fn new(title: &str, platform: &str, path: PathBuf) -> GameEntry {
+        GameEntry {
+            id: 0,
+            title: String::from(title),
+            platform: String::from(platform),
+            path,
+            cover: None,
+            tags: vec![],
+            added: 0,
+            description: String::new(),
+            favorite: false,
+            play_time: 0,
+            last_played: None,
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Adds a finished session of `seconds` to the play time.
+    pub fn record_session(&mut self, seconds: u64) {
+        self.play_time += seconds;
+        self.last_played = Some(unix_now());
+    }
+}
+
+#[derive(Debug)]
+pub enum LibraryError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+    UnsupportedVersion(u32),
+    NotFound(u64),
+}
+
+impl fmt::Display for LibraryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LibraryError::Io(err) => write!(f, "library file error: {}", err),
+            LibraryError::Parse(err) => write!(f, "malformed library file: {}", err),
+            LibraryError::Serialize(err) => write!(f, "can't serialize library: {}", err),
+            LibraryError::UnsupportedVersion(v) => write!(
+                f, "library version {} is newer than supported ({})", v, LIBRARY_VERSION
+            ),
+            LibraryError::NotFound(id) => write!(f, "no game with id {}", id),
+        }
+    }
+}
+
+impl std::error::Error for LibraryError {}
+
+impl From<io::Error> for LibraryError {
+    fn from(err: io::Error) -> Self {
+        LibraryError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for LibraryError {
+    fn from(err: toml::de::Error) -> Self {
+        LibraryError::Parse(err)
+    }
+}
+
+impl From<toml::ser::Error> for LibraryError {
+    fn from(err: toml::ser::Error) -> Self {
+        LibraryError::Serialize(err)
+    }
+}
+
+/// A folder scanned for games. When `platform` is set, every game found inside
+/// it is registered with that platform.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LibraryFolder {
+    pub path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<String>,
+    #[serde(default = "default_recursive")]
+    pub recursive: bool,
+}
+
+fn default_recursive() -> bool {
+    true
+}
+
+impl LibraryFolder {
+    pub fn new(path: PathBuf) -> LibraryFolder {
+        LibraryFolder {
+            path,
+            platform: None,
+            recursive: true,
+        }
+    }
+}
+
+/// On-disk layout of the library file.
+#[derive(Serialize, Deserialize)]
+struct LibraryFile {
+    version: u32,
+    #[serde(default)]
+    next_id: u64,
+    #[serde(default, rename = "folder")]
+    folders: Vec<LibraryFolder>,
+    #[serde(default, rename = "game")]
+    games: Vec<GameEntry>,
+}
+
+/// ## Game Library
+/// Registry of all games known by Starframe. It's stored as a versioned TOML file,
+/// by default on `$XDG_DATA_HOME/starframe/library.toml`.
+///
+/// ```rust
+/// let mut library = GameLibrary::open_default()?;
+/// let id = library.add(GameEntry::new("Paper Mario", "n64", PathBuf::from("roms/paper_mario.z64")));
+/// library.update(id, |game| game.tags.push(String::from("rpg")))?;
+/// library.save()?;
+/// ```
+///
+/// Changes only reach the disk when `save` is called, and never on a read-only
+/// library.
+#[derive(Clone)]
+pub struct GameLibrary {
+    path: PathBuf,
+    next_id: u64,
+    folders: Vec<LibraryFolder>,
+    games: Vec<GameEntry>,
+    /// Bumped on every change, see `revision`.
+    revision: u64,
+    read_only: bool,
+}
+
+impl GameLibrary {
+    /// Creates an empty library that will be saved on `path`.
+    pub fn new(path: PathBuf) -> GameLibrary {
+        GameLibrary {
+            path,
+            next_id: 1,
+            folders: vec![],
+            games: vec![],
+            revision: 0,
+            read_only: false,
+        }
+    }
+
+    /// Default location of the library file.
+    pub fn default_path() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("starframe")
+            .join("library.toml")
+    }
+
+    /// Opens the library on default location. See `GameLibrary::open`.
+    pub fn open_default() -> Result<GameLibrary, LibraryError> {
+        GameLibrary::open(GameLibrary::default_path())
+    }
+
+    /// Reads the library stored on `path`. A missing file isn't an error,
+    /// it just gives an empty library.
+    pub fn open(path: PathBuf) -> Result<GameLibrary, LibraryError> {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(GameLibrary::new(path)),
+            Err(err) => return Err(err.into()),
+        };
+
+        let file: LibraryFile = toml::from_str(&content)?;
+        if file.version > LIBRARY_VERSION {
+            return Err(LibraryError::UnsupportedVersion(file.version));
+        }
+
+        let highest_id = file.games.iter().map(|g| g.id).max().unwrap_or(0);
+        Ok(GameLibrary {
+            path,
+            next_id: file.next_id.max(highest_id + 1),
+            folders: file.folders,
+            games: file.games,
+            revision: 0,
+            read_only: false,
+        })
+    }
+
+    /// Writes the library back to its file. The content goes to a temporary file
+    /// first, so a crash in the middle of it doesn't leave a truncated library.
+    pub fn save(&self) -> Result<(), LibraryError> {
+        if self.read_only {
+            return Ok(());
+        }
+        let file = LibraryFile {
+            version: LIBRARY_VERSION,
+            next_id: self.next_id,
+            folders: self.folders.clone(),
+            games: self.games.clone(),
+        };
+        let content = toml::to_string(&file)?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp_path = self.path.with_extension("toml.tmp");
+        fs::write(&temp_path, content)?;
+        fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Makes `save` leave the file untouched, like when it failed to load and
+    /// an empty library stands in for it.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Grows every time games or folders change, so copies of them can be
+    /// told apart from the current ones.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Folders scanned for games, see `scanner::scan_library`.
+    pub fn folders(&self) -> &[LibraryFolder] {
+        &self.folders
+    }
+
+    /// Adds a folder to be scanned. Returns `false` if it was already there.
+    pub fn add_folder(&mut self, folder: LibraryFolder) -> bool {
+        if self.folders.iter().any(|f| f.path == folder.path) {
+            return false;
+        }
+        self.folders.push(folder);
+        self.revision += 1;
+        true
+    }
+
+    pub fn remove_folder(&mut self, path: &Path) -> Option<LibraryFolder> {
+        let index = self.folders.iter().position(|f| f.path == path)?;
+        self.revision += 1;
+        Some(self.folders.remove(index))
+    }
+
+    /// Registers a new game and returns the id given to it.
+    pub fn add(&mut self, mut game: GameEntry) -> u64 {
+        game.id = self.next_id;
+        if game.added == 0 {
+            game.added = unix_now();
+        }
+        self.next_id += 1;
+        self.revision += 1;
+        self.games.push(game);
+        self.next_id - 1
+    }
+
+    /// Removes the game with given id, returning it.
+    pub fn remove(&mut self, id: u64) -> Result<GameEntry, LibraryError> {
+        match self.games.iter().position(|g| g.id == id) {
+            Some(index) => {
+                self.revision += 1;
+                Ok(self.games.remove(index))
+            }
+            None => Err(LibraryError::NotFound(id)),
+        }
+    }
+
+    /// Edits the folder on `path` in place. Returns `false` if there's none.
+    pub fn update_folder<F: FnOnce(&mut LibraryFolder)>(&mut self, path: &Path, edit: F) -> bool {
+        match self.folders.iter_mut().find(|f| f.path == path) {
+            Some(folder) => {
+                edit(folder);
+                self.revision += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Edits the game with given id in place. The id itself can't be changed.
+    pub fn update<F: FnOnce(&mut GameEntry)>(&mut self, id: u64, edit: F) -> Result<(), LibraryError> {
+        let game = self.games.iter_mut().find(|g| g.id == id).ok_or(LibraryError::NotFound(id))?;
+        edit(game);
+        game.id = id;
+        self.revision += 1;
+        Ok(())
+    }
+
+    pub fn get(&self, id: u64) -> Option<&GameEntry> {
+        self.games.iter().find(|g| g.id == id)
+    }
+
+    /// All games, in the order they were added.
+    pub fn games(&self) -> &[GameEntry] {
+        &self.games
+    }
+
+    /// Returns every game accepted by `predicate`.
+    pub fn query<P: Fn(&GameEntry) -> bool>(&self, predicate: P) -> Vec<&GameEntry> {
+        self.games.iter().filter(|g| predicate(g)).collect()
+    }
+
+    /// Case insensitive search over titles.
+    pub fn search_title(&self, text: &str) -> Vec<&GameEntry> {
+        let text = text.to_lowercase();
+        self.query(|g| g.title.to_lowercase().contains(&text))
+    }
+
+    pub fn by_platform(&self, platform: &str) -> Vec<&GameEntry> {
+        self.query(|g| g.platform.eq_ignore_ascii_case(platform))
+    }
+
+    pub fn by_tag(&self, tag: &str) -> Vec<&GameEntry> {
+        self.query(|g| g.has_tag(tag))
+    }
+}
+
+/// Seconds since UNIX epoch.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Library file on an empty folder on the temp dir, unique to `name`.
+    fn temp_library(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("starframe-library-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("library.toml")
+    }
+
+    fn paper_mario() -> GameEntry {
+        GameEntry::new("Paper Mario", "n64", PathBuf::from("roms/paper_mario.z64"))
+    }
+
+    #[test]
+    fn adds_updates_and_removes_games() {
+        let mut library = GameLibrary::new(temp_library("edits"));
+        let first = library.add(paper_mario());
+        let second = library.add(GameEntry::new("Crash Bandicoot", "psx", PathBuf::from("roms/crash.cue")));
+        assert_ne!(first, second);
+        assert!(library.get(first).unwrap().added > 0);
+
+        library.update(second, |game| {
+            game.id = 99;
+            game.tags.push(String::from("Platformer"));
+        }).unwrap();
+        assert_eq!(library.get(second).unwrap().id, second);
+        assert_eq!(library.by_tag("platformer").len(), 1);
+        assert_eq!(library.by_platform("N64")[0].id, first);
+        assert_eq!(library.search_title("mario")[0].id, first);
+
+        assert_eq!(library.remove(first).unwrap().title, "Paper Mario");
+        assert!(matches!(library.remove(first), Err(LibraryError::NotFound(id)) if id == first));
+        assert!(matches!(library.update(first, |_| {}), Err(LibraryError::NotFound(_))));
+        assert_eq!(library.games().len(), 1);
+        // Ids of removed games aren't given again.
+        assert!(library.add(paper_mario()) > second);
+    }
+
+    #[test]
+    fn saves_and_opens_again() {
+        let path = temp_library("round-trip");
+        let mut library = GameLibrary::new(path.clone());
+        library.add_folder(LibraryFolder::new(PathBuf::from("roms")));
+        let id = library.add(paper_mario());
+        library.update(id, |game| game.record_session(90)).unwrap();
+        library.add(GameEntry::new("Tux Racer", "pc", PathBuf::from("tuxracer")));
+        library.remove(id).unwrap();
+        library.save().unwrap();
+        assert!(!path.with_extension("toml.tmp").exists());
+
+        let mut opened = GameLibrary::open(path).unwrap();
+        assert_eq!(opened.folders(), library.folders());
+        assert_eq!(opened.games(), library.games());
+        assert!(opened.add(paper_mario()) > id);
+    }
+
+    #[test]
+    fn missing_file_is_an_empty_library() {
+        let library = GameLibrary::open(temp_library("missing")).unwrap();
+        assert!(library.games().is_empty());
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let path = temp_library("newer");
+        fs::write(&path, format!("version = {}\n", LIBRARY_VERSION + 1)).unwrap();
+        match GameLibrary::open(path) {
+            Err(LibraryError::UnsupportedVersion(v)) => assert_eq!(v, LIBRARY_VERSION + 1),
+            other => panic!("opened as {:?}", other.map(|l| l.games().len())),
+        }
+    }
+
+    #[test]
+    fn read_only_library_isnt_saved() {
+        let path = temp_library("read-only");
+        let mut library = GameLibrary::new(path.clone());
+        library.set_read_only(true);
+        library.add(paper_mario());
+        library.save().unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -1,11 +1,52 @@
-/// # Elements
-/// Simple module for some UI abstractions to Starframe.
+//! # Elements
+//! Simple module for some UI abstractions to Starframe.
 
 pub mod buttons;
-pub use buttons::{Button, ButtonStyle, States};
+pub use buttons::{Button, ButtonEvent, ButtonStyle};
+
+pub mod input;
+pub use input::{Action, Binding, Input, InputMap, InputState};
+
+pub mod focus;
+pub use focus::FocusGroup;
+
+pub mod assets;
+pub use assets::{AssetCache, TextureHandle};
+
+pub mod placeholders;
+pub use placeholders::PlaceholderCovers;
 
 pub mod covers;
-pub use covers::Cover;
+pub use covers::{Cover, CoverBook, CoverFit};
+
+pub mod grid;
+pub use grid::CoverGrid;
+
+pub mod context;
+pub use context::AppContext;
+
+pub mod config;
+pub use config::{Config, InputConfig, LibraryConfig, Theme, WindowConfig};
+
+pub mod library;
+pub use library::{GameEntry, GameLibrary, LibraryFolder};
+
+pub mod emulators;
+pub use emulators::{EmulatorError, EmulatorProfile, EmulatorRegistry};
+
+pub mod scanner;
+pub use scanner::scan_library;
+
+pub mod launcher;
+pub use launcher::Launcher;
+
+pub mod plugins;
+pub use plugins::PluginHost;
+
+pub mod transitions;
+pub use transitions::{SlideDirection, Transition};
+
+pub mod dialogs;
 
 pub mod scenes;
-pub use scenes::{SceneManager, AsScene, SceneCommand};
\ No newline at end of file
+pub use scenes::{SceneManager, AsScene, SceneCommand, ScenePayload};
\ No newline at end of file
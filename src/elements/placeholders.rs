@@ -0,0 +1,138 @@
+use crate::elements::GameEntry;
+use raylib::prelude::*;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Version of the placeholder design. Bump it when the drawing changes, so
+/// covers cached by older versions are drawn again.
+const PLACEHOLDER_VERSION: u64 = 1;
+
+const WIDTH: i32 = 300;
+const HEIGHT: i32 = 400;
+const MARGIN: i32 = 24;
+const TITLE_SIZE: i32 = 30;
+const BADGE_SIZE: i32 = 20;
+
+/// ## Placeholder Covers
+/// Draws covers for games without artwork: a gradient with colors picked from
+/// the title, the title itself and a badge with the platform. Covers are saved
+/// as PNG files on a cache folder the first time they're asked for, and are
+/// loaded like any other artwork:
+///
+/// ```rust
+/// let placeholders = PlaceholderCovers::new(PlaceholderCovers::default_path());
+/// let path = placeholders.cover_for(&game)?;
+/// let handle = assets.acquire(&path);
+/// ```
+///
+/// Drawing text on images reads the default font from the GPU, so covers must
+/// be drawn on the main thread, after the window is open.
+pub struct PlaceholderCovers {
+    dir: PathBuf,
+}
+
+impl PlaceholderCovers {
+    pub fn new(dir: PathBuf) -> PlaceholderCovers {
+        PlaceholderCovers { dir }
+    }
+
+    /// Default folder for generated covers.
+    pub fn default_path() -> PathBuf {
+        dirs::cache_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("starframe")
+            .join("covers")
+    }
+
+    /// Where the placeholder cover of `game` is cached, drawn or not. Games with
+    /// the same title and platform share the cover.
+    pub fn path_for(&self, game: &GameEntry) -> PathBuf {
+        self.dir.join(format!("{:016x}-{}.png", cover_hash(game), PLACEHOLDER_VERSION))
+    }
+
+    /// Whether the placeholder cover of `game` is drawn already.
+    pub fn is_cached(&self, game: &GameEntry) -> bool {
+        self.path_for(game).exists()
+    }
+
+    /// Path to the placeholder cover of `game`, drawing it if it isn't cached.
+    /// Drawing takes a few milliseconds, so scenes with many games should only
+    /// draw a few per frame.
+    pub fn cover_for(&self, game: &GameEntry) -> Result<PathBuf, io::Error> {
+        let path = self.path_for(game);
+        if path.exists() {
+            return Ok(path);
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        draw_cover(&game.title, &game.platform, cover_hash(game)).export_image(&path.to_string_lossy());
+        // Raylib only logs when exporting fails.
+        if !path.exists() {
+            return Err(io::Error::other(format!("can't write placeholder cover {}", path.display())));
+        }
+        Ok(path)
+    }
+}
+
+fn cover_hash(game: &GameEntry) -> u64 {
+    fnv1a(&[game.title.as_bytes(), b"\0", game.platform.as_bytes()])
+}
+
+/// FNV-1a hash of `parts`. Unlike `DefaultHasher`, it never changes between
+/// builds, so it can name files.
+fn fnv1a(parts: &[&[u8]]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in parts.iter().flat_map(|part| part.iter()) {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+fn draw_cover(title: &str, platform: &str, hash: u64) -> Image {
+    let hue = (hash % 360) as f32;
+    let top = Color::color_from_hsv(hue, 0.55, 0.85);
+    let bottom = Color::color_from_hsv((hue + 40.0) % 360.0, 0.75, 0.3);
+    let mut image = Image::gen_image_gradient_v(WIDTH, HEIGHT, top, bottom);
+
+    // Title, centered on the space above the badge.
+    let lines = wrap_text(title, TITLE_SIZE, WIDTH - 2 * MARGIN);
+    let line_height = TITLE_SIZE + 6;
+    let mut y = (HEIGHT - BADGE_SIZE - 2 * MARGIN) / 2 - lines.len() as i32 * line_height / 2;
+    for line in &lines {
+        let x = WIDTH / 2 - measure_text(line, TITLE_SIZE) / 2;
+        image.draw_text(line, x + 2, y + 2, TITLE_SIZE, Color::BLACK.fade(0.4));
+        image.draw_text(line, x, y, TITLE_SIZE, Color::WHITE);
+        y += line_height;
+    }
+
+    // Platform badge, on the bottom left corner.
+    let badge = platform.to_uppercase();
+    let badge_width = measure_text(&badge, BADGE_SIZE) + 16;
+    let badge_y = HEIGHT - MARGIN - BADGE_SIZE - 8;
+    image.draw_rectangle(MARGIN, badge_y, badge_width, BADGE_SIZE + 8, Color::BLACK.fade(0.6));
+    image.draw_text(&badge, MARGIN + 8, badge_y + 4, BADGE_SIZE, Color::WHITE);
+
+    image
+}
+
+/// Splits `text` on lines up to `width` pixels wide. Words wider than a whole
+/// line get one of their own.
+pub(crate) fn wrap_text(text: &str, font_size: i32, width: i32) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+        if measure_text(&candidate, font_size) > width && !line.is_empty() {
+            lines.push(line);
+            line = word.to_string();
+        } else {
+            line = candidate;
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
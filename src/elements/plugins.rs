@@ -0,0 +1,478 @@
+use crate::elements::{EmulatorProfile, GameEntry, GameLibrary};
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use std::cell::RefCell;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// Operations a script can run on a single call before being stopped.
+/// It keeps a looping script from freezing the render loop.
+pub const MAX_OPERATIONS: u64 = 200_000;
+
+/// An error raised while loading or running a plugin.
+#[derive(Debug, Clone)]
+pub struct PluginError {
+    pub plugin: String,
+    pub message: String,
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "plugin \"{}\": {}", self.plugin, self.message)
+    }
+}
+
+impl std::error::Error for PluginError {}
+
+/// An entry added by a plugin to the menu. Triggering it calls `function`
+/// on the plugin's script.
+#[derive(Debug, Clone)]
+pub struct MenuAction {
+    pub plugin: String,
+    pub label: String,
+    function: String,
+}
+
+/// State shared between the host and the functions exposed to scripts.
+#[derive(Default)]
+struct PluginState {
+    current_plugin: String,
+    /// A plugin's top level code is running, see `PluginHost::load`.
+    loading: bool,
+    /// Read-only copy of the library, see `PluginHost::set_library`.
+    library: Option<GameLibrary>,
+    emulators: Vec<EmulatorProfile>,
+    actions: Vec<MenuAction>,
+}
+
+impl PluginState {
+    /// Games of the library picked by `find`, as maps for scripts.
+    fn find_games<F: Fn(&GameLibrary) -> Vec<&GameEntry>>(&self, find: F) -> Array {
+        match &self.library {
+            Some(library) => find(library).into_iter().map(game_to_map).collect(),
+            None => Array::new(),
+        }
+    }
+}
+
+struct Plugin {
+    name: String,
+    ast: AST,
+    scope: Scope<'static>,
+}
+
+/// ## Plugin Host
+/// Runs plugins written in [Rhai](https://rhai.rs/). Every `*.rhai` file in the
+/// plugins folder (by default `$XDG_DATA_HOME/starframe/plugins`) is a plugin,
+/// and its top level code runs once when it's loaded.
+///
+/// Scripts are sandboxed: they can't import modules nor use `eval`, and every call
+/// is limited to `MAX_OPERATIONS`. Errors never panic, they are collected and can
+/// be fetched with `take_errors` to be shown on screen. The API seen by scripts is:
+///
+/// ```rust
+/// // List of games on the library, as maps with id, title, platform, path and tags.
+/// let games = games();
+///
+/// // Games whose title contains a text, and games of a platform or with a tag.
+/// // None of them care about case.
+/// let marios = search_games("mario");
+/// let n64 = games_on_platform("n64");
+/// let rpgs = games_with_tag("rpg");
+///
+/// // Registers an emulator profile, see `EmulatorProfile`. It only works on top
+/// // level code, since profiles are read once every plugin is loaded.
+/// register_emulator(#{
+///     name: "Snes9x", executable: "snes9x", arguments: "{rom}",
+///     platforms: ["snes"], extensions: ["sfc", "smc"]
+/// });
+///
+/// // Adds an entry to the menu that calls `say_hi` when triggered.
+/// add_menu_action("Say hi", "say_hi");
+/// fn say_hi() { print("hi!"); }
+///
+/// // Called when a scene is loaded or unloaded, with the scene name.
+/// fn on_load(scene) {}
+/// fn on_unload(scene) {}
+///
+/// // Called when a scene is covered by another one pushed over it, and when
+/// // it's back on top.
+/// fn on_pause(scene) {}
+/// fn on_resume(scene) {}
+///
+/// // Called once when Starframe is closing.
+/// fn on_shutdown() {}
+/// ```
+pub struct PluginHost {
+    directory: PathBuf,
+    engine: Engine,
+    plugins: Vec<Plugin>,
+    state: Rc<RefCell<PluginState>>,
+    errors: Vec<PluginError>,
+    /// Names of plugins skipped by `load_all`.
+    disabled: Vec<String>,
+}
+
+impl PluginHost {
+    /// Creates a host without plugins, that will look for them on `directory`.
+    pub fn new(directory: PathBuf) -> PluginHost {
+        let state = Rc::new(RefCell::new(PluginState::default()));
+
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_call_levels(32);
+        engine.set_max_expr_depths(64, 32);
+        engine.set_max_string_size(64 * 1024);
+        engine.set_max_array_size(10_000);
+        engine.set_max_map_size(10_000);
+        engine.set_module_resolver(DummyModuleResolver::new());
+        engine.disable_symbol("eval");
+
+        let print_state = Rc::clone(&state);
+        engine.on_print(move |text| println!("[{}] {}", print_state.borrow().current_plugin, text));
+
+        let games_state = Rc::clone(&state);
+        engine.register_fn("games", move || -> Array {
+            games_state.borrow().find_games(|library| library.games().iter().collect())
+        });
+
+        let search_state = Rc::clone(&state);
+        engine.register_fn("search_games", move |text: &str| -> Array {
+            search_state.borrow().find_games(|library| library.search_title(text))
+        });
+
+        let platform_state = Rc::clone(&state);
+        engine.register_fn("games_on_platform", move |platform: &str| -> Array {
+            platform_state.borrow().find_games(|library| library.by_platform(platform))
+        });
+
+        let tag_state = Rc::clone(&state);
+        engine.register_fn("games_with_tag", move |tag: &str| -> Array {
+            tag_state.borrow().find_games(|library| library.by_tag(tag))
+        });
+
+        let emulators_state = Rc::clone(&state);
+        engine.register_fn("register_emulator", move |map: Map| -> Result<(), Box<EvalAltResult>> {
+            if !emulators_state.borrow().loading {
+                return Err("register_emulator only works while the plugin loads, on top level code".into());
+            }
+            let profile = map_to_profile(&map)?;
+            emulators_state.borrow_mut().emulators.push(profile);
+            Ok(())
+        });
+
+        let actions_state = Rc::clone(&state);
+        engine.register_fn("add_menu_action", move |label: &str, function: &str| {
+            let mut state = actions_state.borrow_mut();
+            let plugin = state.current_plugin.clone();
+            state.actions.push(MenuAction {
+                plugin,
+                label: String::from(label),
+                function: String::from(function),
+            });
+        });
+
+        PluginHost {
+            directory,
+            engine,
+            plugins: vec![],
+            state,
+            errors: vec![],
+            disabled: vec![],
+        }
+    }
+
+    pub fn default_directory() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("starframe")
+            .join("plugins")
+    }
+
+    /// Makes `load_all` skip the plugins with these names.
+    pub fn set_disabled(&mut self, names: Vec<String>) {
+        self.disabled = names;
+    }
+
+    /// Plugin files on the plugins folder, in alphabetical order. A missing
+    /// folder just means there are no plugins.
+    fn plugin_files(&self) -> Vec<PathBuf> {
+        let entries = match fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(_) => return vec![],
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|e| e == "rhai"))
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    /// Names of every plugin on the plugins folder, loaded or not.
+    pub fn available_plugins(&self) -> Vec<String> {
+        self.plugin_files().iter()
+            .filter_map(|path| path.file_stem())
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    /// Loads every plugin on the plugins folder that isn't disabled, in
+    /// alphabetical order.
+    pub fn load_all(&mut self) {
+        for path in self.plugin_files() {
+            let disabled = path.file_stem()
+                .is_some_and(|name| self.disabled.iter().any(|d| *d == name.to_string_lossy()));
+            if !disabled {
+                self.load(path);
+            }
+        }
+    }
+
+    /// Compiles and runs a single plugin. On failure, the error is kept and
+    /// the plugin is dropped.
+    pub fn load(&mut self, path: PathBuf) {
+        let name = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+
+        let ast = match self.engine.compile_file(path) {
+            Ok(ast) => ast,
+            Err(err) => return self.report(&name, err.to_string()),
+        };
+
+        let mut scope = Scope::new();
+        self.state.borrow_mut().current_plugin = name.clone();
+        self.state.borrow_mut().loading = true;
+        let result = self.engine.run_ast_with_scope(&mut scope, &ast);
+        self.state.borrow_mut().loading = false;
+        if let Err(err) = result {
+            self.state.borrow_mut().actions.retain(|a| a.plugin != name);
+            return self.report(&name, err.to_string());
+        }
+
+        self.plugins.push(Plugin { name, ast, scope });
+    }
+
+    /// Names of the loaded plugins.
+    pub fn plugin_names(&self) -> Vec<&str> {
+        self.plugins.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    /// Updates the games seen by scripts through `games()` and the search
+    /// functions. Scripts get a copy of `library`, which is never saved.
+    pub fn set_library(&mut self, library: &GameLibrary) {
+        let mut copy = library.clone();
+        copy.set_read_only(true);
+        self.state.borrow_mut().library = Some(copy);
+    }
+
+    /// Emulator profiles registered by plugins since last call. Plugins only
+    /// register them while loading, so a call after `load_all` gets them all.
+    pub fn take_emulators(&mut self) -> Vec<EmulatorProfile> {
+        std::mem::take(&mut self.state.borrow_mut().emulators)
+    }
+
+    pub fn menu_actions(&self) -> Vec<MenuAction> {
+        self.state.borrow().actions.clone()
+    }
+
+    /// Runs the menu action at `index` of `menu_actions`.
+    pub fn run_action(&mut self, index: usize) {
+        let action = match self.state.borrow().actions.get(index) {
+            Some(action) => action.clone(),
+            None => return,
+        };
+        self.call(&action.plugin, &action.function, ());
+    }
+
+    /// Notifies plugins of a scene lifecycle event, calling `on_<event>(scene)`
+    /// on every plugin that defines it.
+    pub fn emit(&mut self, event: &str, scene: &str) {
+        let function = format!("on_{}", event);
+        let listeners: Vec<String> = self.plugins.iter()
+            .filter(|p| p.ast.iter_functions().any(|f| f.name == function && f.params.len() == 1))
+            .map(|p| p.name.clone())
+            .collect();
+
+        for plugin in listeners {
+            self.call(&plugin, &function, (String::from(scene),));
+        }
+    }
+
+    /// Calls `on_shutdown()` on every plugin that defines it, as Starframe
+    /// is closing.
+    pub fn shutdown(&mut self) {
+        let listeners: Vec<String> = self.plugins.iter()
+            .filter(|p| p.ast.iter_functions().any(|f| f.name == "on_shutdown" && f.params.is_empty()))
+            .map(|p| p.name.clone())
+            .collect();
+
+        for plugin in listeners {
+            self.call(&plugin, "on_shutdown", ());
+        }
+    }
+
+    /// Errors raised by plugins since last call.
+    pub fn take_errors(&mut self) -> Vec<PluginError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn call(&mut self, plugin: &str, function: &str, args: impl rhai::FuncArgs) {
+        let plugin = match self.plugins.iter_mut().find(|p| p.name == plugin) {
+            Some(plugin) => plugin,
+            None => return,
+        };
+
+        self.state.borrow_mut().current_plugin = plugin.name.clone();
+        // Top level code already ran on `load`, only the function is evaluated.
+        let options = CallFnOptions::new().eval_ast(false);
+        let result = self.engine.call_fn_with_options::<Dynamic>(options, &mut plugin.scope, &plugin.ast, function, args);
+        if let Err(err) = result {
+            let name = plugin.name.clone();
+            self.report(&name, err.to_string());
+        }
+    }
+
+    fn report(&mut self, plugin: &str, message: String) {
+        let error = PluginError {
+            plugin: String::from(plugin),
+            message,
+        };
+        println!("{}", error);
+        self.errors.push(error);
+    }
+}
+
+fn game_to_map(game: &GameEntry) -> Dynamic {
+    let mut map = Map::new();
+    map.insert("id".into(), Dynamic::from(game.id as i64));
+    map.insert("title".into(), game.title.clone().into());
+    map.insert("platform".into(), game.platform.clone().into());
+    map.insert("path".into(), game.path.to_string_lossy().into_owned().into());
+    map.insert("tags".into(), game.tags.iter().cloned().map(Dynamic::from).collect::<Array>().into());
+    map.into()
+}
+
+fn map_to_profile(map: &Map) -> Result<EmulatorProfile, Box<EvalAltResult>> {
+    let text = |key: &str| -> Result<String, Box<EvalAltResult>> {
+        map.get(key)
+            .and_then(|value| value.clone().into_string().ok())
+            .ok_or_else(|| format!("emulator needs a \"{}\" text", key).into())
+    };
+    let list = |key: &str| -> Vec<String> {
+        map.get(key)
+            .and_then(|value| value.clone().into_typed_array::<String>().ok())
+            .unwrap_or_default()
+    };
+
+    let mut profile = EmulatorProfile::new(&text("name")?, PathBuf::from(text("executable")?), &text("arguments")?);
+    profile.platforms = list("platforms");
+    profile.extensions = list("extensions");
+    Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Host for a single plugin called `name`, written on an empty folder on
+    /// the temp dir.
+    fn host_with(name: &str, script: &str) -> PluginHost {
+        let dir = std::env::temp_dir().join(format!("starframe-plugins-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(format!("{}.rhai", name)), script).unwrap();
+
+        let mut host = PluginHost::new(dir);
+        host.load_all();
+        host
+    }
+
+    #[test]
+    fn operation_limit_stops_endless_loops() {
+        let mut host = host_with("spin", "add_menu_action(\"Spin\", \"spin\");\nfn spin() { loop {} }");
+        assert!(host.take_errors().is_empty());
+
+        host.run_action(0);
+        let errors = host.take_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].plugin, "spin");
+
+        let mut host = host_with("spin_on_load", "loop {}");
+        assert_eq!(host.take_errors().len(), 1);
+        assert!(host.plugin_names().is_empty());
+    }
+
+    #[test]
+    fn eval_is_unavailable() {
+        let mut host = host_with("evil", "eval(\"print(1)\");");
+        let errors = host.take_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("'eval' is disabled"), "{}", errors[0]);
+        assert!(host.plugin_names().is_empty());
+    }
+
+    #[test]
+    fn script_errors_are_collected() {
+        let mut host = host_with("broken", "fn on_load(scene) { throw \"no \" + scene; }");
+        assert_eq!(host.plugin_names(), ["broken"]);
+
+        host.emit("load", "Menu");
+        host.emit("unload", "Menu");
+        let errors = host.take_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("no Menu"), "{}", errors[0]);
+        assert!(host.take_errors().is_empty());
+
+        let mut host = host_with("malformed", "fn on_load(scene {");
+        assert_eq!(host.take_errors().len(), 1);
+        assert!(host.plugin_names().is_empty());
+    }
+
+    #[test]
+    fn emulators_are_only_registered_while_loading() {
+        let emulator = "#{ name: \"Stub\", executable: \"true\", arguments: \"{rom}\" }";
+        let script = format!("register_emulator({0});\nfn on_load(scene) {{ register_emulator({0}); }}", emulator);
+        let mut host = host_with("emulators", &script);
+        assert_eq!(host.take_emulators().len(), 1);
+
+        host.emit("load", "Menu");
+        assert!(host.take_emulators().is_empty());
+        assert_eq!(host.take_errors().len(), 1);
+    }
+
+    #[test]
+    fn scripts_see_the_library_set_last() {
+        let mut host = host_with("count", "add_menu_action(\"Count\", \"count\");\nfn count() { throw games().len(); }");
+        let mut library = GameLibrary::new(PathBuf::from("unused.toml"));
+        library.add(GameEntry::new("Paper Mario", "n64", PathBuf::from("paper_mario.z64")));
+        host.set_library(&library);
+        host.run_action(0);
+        host.set_library(&GameLibrary::new(PathBuf::from("unused.toml")));
+        host.run_action(0);
+
+        let errors = host.take_errors();
+        assert!(errors[0].message.contains('1') && errors[1].message.contains('0'), "{:?}", errors);
+    }
+
+    #[test]
+    fn scripts_search_the_library() {
+        let script = "add_menu_action(\"Search\", \"search\");\n\
+            fn search() { throw `${search_games(\"MARIO\").len()} ${games_on_platform(\"n64\").len()} ${games_with_tag(\"rpg\").len()}`; }";
+        let mut host = host_with("search", script);
+        let mut library = GameLibrary::new(PathBuf::from("unused.toml"));
+        library.add(GameEntry::new("Paper Mario", "n64", PathBuf::from("paper_mario.z64")));
+        library.add(GameEntry::new("Super Mario World", "snes", PathBuf::from("smw.sfc")));
+        let mut zelda = GameEntry::new("Zelda", "n64", PathBuf::from("zelda.z64"));
+        zelda.tags.push(String::from("RPG"));
+        library.add(zelda);
+        host.set_library(&library);
+        host.run_action(0);
+
+        let errors = host.take_errors();
+        assert!(errors[0].message.contains("2 2 1"), "{:?}", errors);
+    }
+}
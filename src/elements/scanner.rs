@@ -0,0 +1,550 @@
+use crate::elements::{EmulatorProfile, EmulatorRegistry, GameEntry, GameLibrary, LibraryFolder};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Extensions of image files used as covers when they sit next to a game
+/// with the same name.
+const COVER_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+const ZIP_END_SIGNATURE: [u8; 4] = [0x50, 0x4b, 0x05, 0x06];
+const ZIP_ENTRY_SIGNATURE: [u8; 4] = [0x50, 0x4b, 0x01, 0x02];
+/// Size of the end of central directory record, without its comment.
+const ZIP_END_SIZE: usize = 22;
+/// Size of a central directory entry, without its name, extra field and comment.
+const ZIP_ENTRY_SIZE: usize = 46;
+
+/// A game that changed its path between two scans.
+#[derive(Debug, Clone)]
+pub struct MovedGame {
+    pub id: u64,
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Everything a scan did to the library.
+#[derive(Debug, Default)]
+pub struct ScanReport {
+    /// Ids of the games registered by the scan.
+    pub added: Vec<u64>,
+    pub removed: Vec<GameEntry>,
+    pub moved: Vec<MovedGame>,
+    /// Folders or files that couldn't be read.
+    pub errors: Vec<String>,
+}
+
+impl ScanReport {
+    pub fn has_changes(&self) -> bool {
+        !self.added.is_empty() || !self.removed.is_empty() || !self.moved.is_empty()
+    }
+}
+
+impl fmt::Display for ScanReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, "{} added, {} removed, {} moved",
+            self.added.len(), self.removed.len(), self.moved.len()
+        )?;
+        if !self.errors.is_empty() {
+            write!(f, " ({} errors)", self.errors.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// A game file found on disk, not registered yet.
+struct FoundGame {
+    path: PathBuf,
+    title: String,
+    platform: String,
+    cover: Option<PathBuf>,
+}
+
+/// ## Scan Library
+/// Walks every folder of `library` looking for files that some emulator profile
+/// on `emulators` can run, and brings the library up to date:
+///
+/// - new files are registered, with a title taken from the file name;
+/// - games whose file vanished but reappeared with the same name somewhere else
+///   are moved;
+/// - games whose file vanished from a scanned folder are removed.
+///
+/// Files referenced by a `.cue` sheet or a `.m3u` playlist are not registered on
+/// their own, and discs of the same game (`Game (Disc 1).chd`, `Game (Disc 2).chd`)
+/// become a single entry pointing to the first disc.
+///
+/// Zip archives that no profile runs as they are are looked into, and match the
+/// profile of the files they hold, so a zipped `.cue` and its tracks become one
+/// game of that platform. Other archives, like `.7z`, are only matched by their
+/// own extension.
+///
+/// The library isn't saved, that's up to the caller.
+pub fn scan_library(library: &mut GameLibrary, emulators: &EmulatorRegistry) -> ScanReport {
+    let mut report = ScanReport::default();
+    let mut found = vec![];
+    let mut reachable_folders = vec![];
+
+    for folder in library.folders() {
+        if !folder.path.is_dir() {
+            report.errors.push(format!("{} is not reachable", folder.path.display()));
+            continue;
+        }
+        let mut files = vec![];
+        collect_files(&folder.path, folder.recursive, &mut files, &mut report.errors);
+        files.sort();
+        found.extend(detect_games(folder, &files, emulators));
+        reachable_folders.push(folder.path.clone());
+    }
+
+    // Only games inside folders we could read are considered missing, so an
+    // unmounted drive doesn't wipe its games from the library.
+    let known_paths: HashSet<PathBuf> = library.games().iter().map(|g| g.path.clone()).collect();
+    let mut missing: Vec<(u64, PathBuf)> = library.games().iter()
+        .filter(|g| reachable_folders.iter().any(|folder| g.path.starts_with(folder)))
+        .filter(|g| !g.path.exists())
+        .map(|g| (g.id, g.path.clone()))
+        .collect();
+
+    for game in found {
+        if known_paths.contains(&game.path) {
+            continue;
+        }
+
+        if let Some(index) = missing.iter().position(|(_, old)| old.file_name() == game.path.file_name()) {
+            let (id, from) = missing.remove(index);
+            let to = game.path.clone();
+            let _ = library.update(id, |entry| {
+                entry.path = game.path;
+                if !entry.cover.as_ref().is_some_and(|cover| cover.exists()) {
+                    entry.cover = game.cover;
+                }
+            });
+            report.moved.push(MovedGame { id, from, to });
+            continue;
+        }
+
+        let mut entry = GameEntry::new(&game.title, &game.platform, game.path);
+        entry.cover = game.cover;
+        report.added.push(library.add(entry));
+    }
+
+    for (id, _) in missing {
+        if let Ok(entry) = library.remove(id) {
+            report.removed.push(entry);
+        }
+    }
+    report
+}
+
+fn collect_files(folder: &Path, recursive: bool, files: &mut Vec<PathBuf>, errors: &mut Vec<String>) {
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(err) => {
+            errors.push(format!("{}: {}", folder.display(), err));
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if recursive {
+                collect_files(&path, recursive, files, errors);
+            }
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+/// Picks game files among `files`, which must be sorted.
+fn detect_games(folder: &LibraryFolder, files: &[PathBuf], emulators: &EmulatorRegistry) -> Vec<FoundGame> {
+    let platform_profiles: Vec<_> = match &folder.platform {
+        Some(platform) => emulators.profiles().iter().filter(|p| p.supports_platform(platform)).collect(),
+        None => vec![],
+    };
+    let profiles = if platform_profiles.is_empty() {
+        emulators.profiles().iter().collect()
+    } else {
+        platform_profiles
+    };
+
+    // Tracks of cue sheets and discs of playlists belong to them.
+    let mut consumed: HashSet<PathBuf> = HashSet::new();
+    for file in files {
+        let references = match extension_of(file).as_deref() {
+            Some("cue") => cue_references(file),
+            Some("m3u") => playlist_references(file),
+            _ => continue,
+        };
+        let folder = file.parent().unwrap_or_else(|| Path::new(""));
+        consumed.extend(references.into_iter().map(|reference| folder.join(reference)));
+    }
+
+    let mut games = vec![];
+    let mut discs: HashSet<(PathBuf, String)> = HashSet::new();
+    for file in files {
+        if consumed.contains(file) {
+            continue;
+        }
+        let profile = match find_profile(&profiles, file) {
+            Some(profile) => profile,
+            None => continue,
+        };
+
+        let stem = file.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let title = clean_title(&stem);
+        if disc_number(&stem).is_some() {
+            let key = (file.parent().map(Path::to_path_buf).unwrap_or_default(), title.to_lowercase());
+            if !discs.insert(key) {
+                continue;
+            }
+        }
+
+        let platform = folder.platform.clone()
+            .or_else(|| profile.platforms.first().cloned())
+            .unwrap_or_else(|| profile.name.to_lowercase());
+
+        games.push(FoundGame {
+            path: file.clone(),
+            title,
+            platform,
+            cover: find_cover(file),
+        });
+    }
+    games
+}
+
+/// Profile that runs `file`. Zip archives no profile takes as they are are
+/// matched by the files inside them.
+fn find_profile<'a>(profiles: &[&'a EmulatorProfile], file: &Path) -> Option<&'a EmulatorProfile> {
+    if let Some(profile) = profiles.iter().find(|p| p.supports_file(file)) {
+        return Some(profile);
+    }
+    if extension_of(file).as_deref() != Some("zip") {
+        return None;
+    }
+    let entries = zip_entries(file).ok()?;
+    profiles.iter().copied().find(|p| entries.iter().any(|entry| p.supports_file(Path::new(entry))))
+}
+
+/// Names of the files inside a zip archive, read from its central directory
+/// without decompressing anything. Zip64 archives aren't supported.
+fn zip_entries(path: &Path) -> io::Result<Vec<String>> {
+    let malformed = || io::Error::new(io::ErrorKind::InvalidData, "malformed zip archive");
+    let mut file = fs::File::open(path)?;
+    let length = file.metadata()?.len();
+
+    // The end record is the last thing on the file, followed by a comment of
+    // up to 64KiB.
+    let tail_length = length.min((ZIP_END_SIZE + u16::MAX as usize) as u64);
+    file.seek(SeekFrom::Start(length - tail_length))?;
+    let mut tail = vec![0; tail_length as usize];
+    file.read_exact(&mut tail)?;
+    let last = tail.len().checked_sub(ZIP_END_SIZE).ok_or_else(malformed)?;
+    let end = (0..=last).rev()
+        .find(|at| tail[*at..*at + 4] == ZIP_END_SIGNATURE)
+        .ok_or_else(malformed)?;
+
+    let count = u16_at(&tail, end + 10) as usize;
+    let directory_size = u32_at(&tail, end + 12) as u64;
+    let directory_offset = u32_at(&tail, end + 16) as u64;
+    if directory_offset + directory_size > length {
+        return Err(malformed());
+    }
+    file.seek(SeekFrom::Start(directory_offset))?;
+    let mut directory = vec![0; directory_size as usize];
+    file.read_exact(&mut directory)?;
+
+    let mut names = vec![];
+    let mut at = 0;
+    for _ in 0..count {
+        let header = directory.get(at..at + ZIP_ENTRY_SIZE).ok_or_else(malformed)?;
+        if header[..4] != ZIP_ENTRY_SIGNATURE {
+            return Err(malformed());
+        }
+        let name_length = u16_at(header, 28) as usize;
+        let skipped = u16_at(header, 30) as usize + u16_at(header, 32) as usize;
+        let name = directory.get(at + ZIP_ENTRY_SIZE..at + ZIP_ENTRY_SIZE + name_length).ok_or_else(malformed)?;
+        let name = String::from_utf8_lossy(name);
+        // Folders are entries too, ending with a slash.
+        if !name.ends_with('/') {
+            names.push(name.into_owned());
+        }
+        at += ZIP_ENTRY_SIZE + name_length + skipped;
+    }
+    Ok(names)
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+fn extension_of(path: &Path) -> Option<String> {
+    path.extension().map(|e| e.to_string_lossy().to_lowercase())
+}
+
+/// Files listed on `FILE` lines of a cue sheet.
+fn cue_references(path: &Path) -> Vec<String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => return vec![],
+    };
+
+    content.lines()
+        .map(str::trim)
+        .filter(|line| line.get(..5).is_some_and(|start| start.eq_ignore_ascii_case("FILE ")))
+        .filter_map(|line| {
+            let rest = line[5..].trim();
+            if let Some(quoted) = rest.strip_prefix('"') {
+                quoted.split('"').next().map(String::from)
+            } else {
+                rest.split_whitespace().next().map(String::from)
+            }
+        })
+        .collect()
+}
+
+/// Non-comment lines of a playlist.
+fn playlist_references(path: &Path) -> Vec<String> {
+    match fs::read_to_string(path) {
+        Ok(content) => content.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(String::from)
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+fn find_cover(file: &Path) -> Option<PathBuf> {
+    COVER_EXTENSIONS.iter()
+        .map(|extension| file.with_extension(extension))
+        .find(|cover| cover.is_file())
+}
+
+/// Number of the disc on names like "Final Fantasy VII (USA) (Disc 2)".
+fn disc_number(stem: &str) -> Option<u32> {
+    let lower = stem.to_lowercase();
+    let start = lower.find("(disc ")? + "(disc ".len();
+    let digits: String = lower[start..].chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+/// Turns a file name in a readable title, dropping region, revision and dump
+/// tags: "Legend of Zelda, The - A Link to the Past (USA) (Rev 1) [!]" becomes
+/// "The Legend of Zelda - A Link to the Past".
+pub fn clean_title(stem: &str) -> String {
+    let mut title = String::new();
+    let mut depth = 0;
+    for c in stem.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' if depth > 0 => depth -= 1,
+            '_' if depth == 0 => title.push(' '),
+            c if depth == 0 => title.push(c),
+            _ => {}
+        }
+    }
+
+    let mut title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    for article in ["The", "A", "An"] {
+        let suffix = format!(", {}", article);
+        if let Some(index) = title.find(&suffix) {
+            let after = &title[index + suffix.len()..];
+            if after.is_empty() || after.starts_with(' ') {
+                title = format!("{} {}{}", article, &title[..index], after);
+                break;
+            }
+        }
+    }
+
+    if title.is_empty() {
+        String::from(stem)
+    } else {
+        title
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty folder on the temp dir, unique to `name`.
+    fn temp_folder(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("starframe-scanner-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn touch(path: &Path) {
+        fs::write(path, b"").unwrap();
+    }
+
+    fn emulators(extensions: &[&str], platform: &str) -> EmulatorRegistry {
+        let mut profile = EmulatorProfile::new("Stub", PathBuf::from("true"), "{rom}");
+        profile.platforms = vec![String::from(platform)];
+        profile.extensions = extensions.iter().map(|e| e.to_string()).collect();
+        let mut emulators = EmulatorRegistry::new(PathBuf::from("emulators.toml"));
+        emulators.add(profile).unwrap();
+        emulators
+    }
+
+    fn library_of(dir: &Path) -> GameLibrary {
+        let mut library = GameLibrary::new(dir.join("library.toml"));
+        library.add_folder(LibraryFolder::new(dir.to_path_buf()));
+        library
+    }
+
+    /// Zip archive holding empty, uncompressed files called `names`.
+    fn write_zip(path: &Path, names: &[&str]) {
+        let (mut local, mut directory) = (vec![], vec![]);
+        for name in names {
+            let offset = local.len() as u32;
+            local.extend_from_slice(&[0x50, 0x4b, 0x03, 0x04, 20, 0, 0, 0, 0, 0]);
+            local.extend_from_slice(&[0; 16]);
+            local.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            local.extend_from_slice(&[0, 0]);
+            local.extend_from_slice(name.as_bytes());
+
+            directory.extend_from_slice(&ZIP_ENTRY_SIGNATURE);
+            directory.extend_from_slice(&[20, 0, 20, 0, 0, 0, 0, 0]);
+            directory.extend_from_slice(&[0; 16]);
+            directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+
+        let mut zip = local;
+        let directory_offset = zip.len() as u32;
+        zip.extend_from_slice(&directory);
+        zip.extend_from_slice(&ZIP_END_SIGNATURE);
+        zip.extend_from_slice(&[0; 4]);
+        zip.extend_from_slice(&(names.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&(names.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        zip.extend_from_slice(&directory_offset.to_le_bytes());
+        zip.extend_from_slice(&[0, 0]);
+        fs::write(path, zip).unwrap();
+    }
+
+    #[test]
+    fn clean_title_drops_tags() {
+        assert_eq!(clean_title("Super Metroid (USA, Europe) (Rev 1) [!]"), "Super Metroid");
+        assert_eq!(clean_title("Chrono_Trigger_(USA)"), "Chrono Trigger");
+        assert_eq!(clean_title("Sonic (Beta [b1])"), "Sonic");
+    }
+
+    #[test]
+    fn clean_title_moves_articles() {
+        assert_eq!(
+            clean_title("Legend of Zelda, The - A Link to the Past (USA) (Rev 1) [!]"),
+            "The Legend of Zelda - A Link to the Past"
+        );
+        assert_eq!(clean_title("Adventure, An"), "An Adventure");
+        assert_eq!(clean_title("Theme, Theatre"), "Theme, Theatre");
+    }
+
+    #[test]
+    fn clean_title_keeps_names_made_of_tags() {
+        assert_eq!(clean_title("(Demo)"), "(Demo)");
+    }
+
+    #[test]
+    fn disc_numbers() {
+        assert_eq!(disc_number("Final Fantasy VII (USA) (Disc 2)"), Some(2));
+        assert_eq!(disc_number("Metal Gear Solid (disc 10)"), Some(10));
+        assert_eq!(disc_number("Discworld (USA)"), None);
+    }
+
+    #[test]
+    fn groups_discs_and_sheets() {
+        let dir = temp_folder("group");
+        for name in ["Game (USA) (Disc 1).chd", "Game (USA) (Disc 2).chd", "Other.bin", "Other.cue"] {
+            touch(&dir.join(name));
+        }
+        fs::write(dir.join("Other.cue"), "FILE \"Other.bin\" BINARY\n").unwrap();
+
+        let mut library = library_of(&dir);
+        let report = scan_library(&mut library, &emulators(&["chd", "cue", "bin"], "psx"));
+        let mut paths: Vec<_> = library.games().iter().map(|g| g.path.file_name().unwrap().to_owned()).collect();
+        paths.sort();
+        assert_eq!(report.added.len(), 2);
+        assert_eq!(paths, ["Game (USA) (Disc 1).chd", "Other.cue"]);
+        assert!(library.games().iter().all(|g| g.platform == "psx"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn detects_moved_and_removed_games() {
+        let dir = temp_folder("moves");
+        fs::create_dir_all(dir.join("old")).unwrap();
+        touch(&dir.join("old").join("Moved (USA).sfc"));
+        touch(&dir.join("Gone.sfc"));
+        let emulators = emulators(&["sfc"], "snes");
+        let mut library = library_of(&dir);
+        assert_eq!(scan_library(&mut library, &emulators).added.len(), 2);
+
+        fs::create_dir_all(dir.join("new")).unwrap();
+        fs::rename(dir.join("old").join("Moved (USA).sfc"), dir.join("new").join("Moved (USA).sfc")).unwrap();
+        fs::remove_file(dir.join("Gone.sfc")).unwrap();
+        let report = scan_library(&mut library, &emulators);
+
+        assert!(report.added.is_empty());
+        assert_eq!(report.moved.len(), 1);
+        assert_eq!(report.moved[0].to, dir.join("new").join("Moved (USA).sfc"));
+        assert_eq!(report.removed.len(), 1);
+        assert_eq!(report.removed[0].title, "Gone");
+        assert_eq!(library.games().len(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unmounted_folders_keep_their_games() {
+        let dir = temp_folder("unmounted");
+        touch(&dir.join("Game.sfc"));
+        let emulators = emulators(&["sfc"], "snes");
+        let mut library = library_of(&dir);
+        scan_library(&mut library, &emulators);
+
+        fs::remove_dir_all(&dir).unwrap();
+        let report = scan_library(&mut library, &emulators);
+        assert!(report.removed.is_empty());
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(library.games().len(), 1);
+    }
+
+    #[test]
+    fn reads_zip_entries() {
+        let dir = temp_folder("zip-entries");
+        let path = dir.join("game.zip");
+        write_zip(&path, &["folder/", "folder/Game.cue", "folder/Game.bin"]);
+        assert_eq!(zip_entries(&path).unwrap(), ["folder/Game.cue", "folder/Game.bin"]);
+
+        fs::write(&path, b"not a zip").unwrap();
+        assert!(zip_entries(&path).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn matches_zips_by_their_contents() {
+        let dir = temp_folder("zip-games");
+        write_zip(&dir.join("Game (Europe).zip"), &["Game (Europe).sfc"]);
+        write_zip(&dir.join("Notes.zip"), &["notes.txt"]);
+
+        let mut library = library_of(&dir);
+        let report = scan_library(&mut library, &emulators(&["sfc"], "snes"));
+        assert_eq!(report.added.len(), 1);
+        assert_eq!(library.games()[0].title, "Game");
+        assert_eq!(library.games()[0].platform, "snes");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
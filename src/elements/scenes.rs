@@ -1,6 +1,10 @@
+use crate::elements::dialogs::{ErrorScene, ExitDialog, EXIT_DIALOG};
+use crate::elements::{AppContext, AssetCache, Config, GameLibrary, Input, InputMap, InputState, PluginHost, Transition};
 use raylib::prelude::*;
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::any::Any;
+use std::fmt;
 
 /// ## Scene Manager 
 /// is the core for scene's creation. It makes simple to load up and load down
@@ -27,9 +31,9 @@ use std::cell::RefCell;
 /// ```
 /// 
 /// After implementing it and pushing to `scene_manager`, you will need to set the
-/// scene manager's current scene with your scene name. Scene name should be 
-/// a `&'static str` inside scene's struct. Pretending that scene name
-/// is "Cool", all we need to do is this:
+/// scene manager's current scene with your scene name. Scene name is usually
+/// a `&'static str` inside scene's struct, but it can be built at runtime too
+/// (see `SceneId`). Pretending that scene name is "Cool", all we need to do is this:
 /// ```rust
 ///     scene_manager.set_current_scene("Cool");
 /// ```
@@ -37,18 +41,192 @@ use std::cell::RefCell;
 /// And then, use `scene_manager.play_scene(...)`. Actually, SceneManager is built with
 /// Raylib-rs backend, so you need to provide both `RaylibHandle` and `RaylibThread` to
 /// use it.
+///
+/// Scenes are switched by `play_scene`, since it's the one holding the handle: the
+/// scene being left is unloaded and the new one is loaded right before its first
+/// update.
+///
+/// ### Scene stack
+/// Scenes can also be stacked, for dialogs and menus shown over another scene.
+/// `SceneCommand::push_scene` pauses the current scene and puts a new one on top,
+/// `SceneCommand::pop_scene` removes the top one and resumes the one below, and
+/// `SceneCommand::replace_scene` swaps only the top one. Only the top scene is
+/// updated and receives input. Scenes below it are drawn dimmed when the top one
+/// is an overlay (see `AsScene::is_overlay`), and aren't drawn at all otherwise.
+///
+/// ### Transitions
+/// Commands that change the stack can carry a `Transition`, played by the manager
+/// between the outgoing and the incoming scenes. Input isn't given to scenes
+/// while a transition plays.
+///
+/// ### Exiting
+/// `SceneCommand::exit_program` doesn't end the process: it sets the exit code
+/// returned by `exit_code`, so the main loop can stop and call `shutdown`,
+/// which unloads every scene and lets plugins clean up. With
+/// `set_confirm_exit`, the user is asked first.
 pub struct SceneManager {
-    pub scene_list: Vec<Rc<RefCell<Option<Box<dyn AsScene>>>>>,
-    pub current_scene: Rc<RefCell<Option<Box<dyn AsScene>>>>,
+    pub scene_list: Vec<SceneRef>,
+    /// Scenes being played, the current one is the last.
+    stack: Vec<SceneRef>,
+    /// Change to the stack waiting to be applied by `play_scene`.
+    pending: Option<StackChange>,
+    /// Payload for the scene brought on top when `pending` is applied.
+    pending_payload: Option<ScenePayload>,
+    /// Transition to play when `pending` is applied, with its duration.
+    pending_transition: Option<(Transition, f32)>,
+    /// Transition being played, with its duration and the seconds elapsed.
+    transition: Option<(Transition, f32, f32)>,
+    /// Render textures of the outgoing and incoming scenes, created on the
+    /// first transition and sized as the screen.
+    targets: Option<(RenderTexture2D, RenderTexture2D)>,
+    /// Services shared by scenes. The input map and the plugin host are read
+    /// from here too.
+    context: AppContext,
+    input: Input,
+    /// Exit code asked by a scene, waiting for confirmation.
+    requested_exit: Option<i32>,
+    exit_code: Option<i32>,
+    confirm_exit: bool,
+    /// First scene set with `set_current_scene`.
+    home_scene: Option<SceneId>,
+    /// Seconds of frame time not simulated yet, see `FIXED_STEP`.
+    accumulator: f32,
+    /// Input of frames that didn't fit a step, waiting for the next one.
+    latched_input: Option<InputState>,
+    /// `GameLibrary::revision` last saved and handed to plugins.
+    library_revision: Option<u64>,
 }
 
+/// An error raised when a scene can't be registered or played.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SceneError {
+    /// No scene on the scene list has the name.
+    NotFound(String),
+    /// A scene with the same name is already on the scene list.
+    Duplicate(String),
+    /// The scene is already on the stack, it can't be pushed twice.
+    AlreadyOnStack(String),
+    /// Nothing is on the stack, `set_current_scene` was never called.
+    NothingToPlay,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::NotFound(name) => write!(f, "there's no scene named \"{}\"", name),
+            SceneError::Duplicate(name) => write!(f, "a scene named \"{}\" already exists", name),
+            SceneError::AlreadyOnStack(name) => write!(f, "scene \"{}\" is already being played", name),
+            SceneError::NothingToPlay => write!(f, "there's no scene to play"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+/// ## Scene Id
+/// Identifies a scene on `SceneManager`, by the name returned by `AsScene::name`.
+/// Names can be built at runtime, so a scene can be registered for a single
+/// thing, like `SceneId::from(format!("Game {}", game.id))`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SceneId(String);
+
+impl SceneId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for SceneId {
+    fn from(name: &str) -> SceneId {
+        SceneId(String::from(name))
+    }
+}
+
+impl From<String> for SceneId {
+    fn from(name: String) -> SceneId {
+        SceneId(name)
+    }
+}
+
+impl fmt::Display for SceneId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// ## Scene Payload
+/// Data handed by a `SceneCommand` to the scene it brings on top, given with
+/// `SceneCommand::with_payload`. Any type can be sent, and the receiving scene
+/// asks for the one it expects:
+///
+/// ```rust
+/// // On the menu:
+/// SceneCommand::push_scene("Game Details").with_payload(GameSelected(game.id))
+///
+/// // On the details scene:
+/// fn receive(&mut self, payload: ScenePayload) {
+///     if let Some(GameSelected(id)) = payload.get::<GameSelected>() {
+///         self.game_id = *id;
+///     }
+/// }
+/// ```
+pub struct ScenePayload(Box<dyn Any>);
+
+impl ScenePayload {
+    pub fn new<T: Any>(value: T) -> ScenePayload {
+        ScenePayload(Box::new(value))
+    }
+
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.0.downcast_ref::<T>()
+    }
+
+    /// Takes the value out if it's a `T`, giving the payload back if it isn't.
+    pub fn take<T: Any>(self) -> Result<T, ScenePayload> {
+        self.0.downcast::<T>().map(|value| *value).map_err(ScenePayload)
+    }
+}
+
+impl fmt::Debug for ScenePayload {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ScenePayload")
+    }
+}
+
+/// A scene shared between the scene list and the stack.
+pub type SceneRef = Rc<RefCell<Option<Box<dyn AsScene>>>>;
+
+/// Seconds scenes advance on each update, whatever the frame rate is.
+pub const FIXED_STEP: f32 = 1.0 / 60.0;
+/// Longest frame time taken into account, so a stall (like dragging the window)
+/// doesn't make scenes run lots of updates to catch up.
+const MAX_FRAME_TIME: f32 = 0.25;
+
+/// How much scenes under an overlay are darkened, from 0.0 to 1.0.
+const OVERLAY_DIM: f32 = 0.6;
+
 /// A little set of commands that can be used with SceneManager.
 enum Command {
-    Exit,
-    JumpToNamedScene,
+    /// Exits with the given code, asking first if confirmation is on.
+    Exit(i32),
+    /// Exits without asking, sent by the exit dialog.
+    ConfirmExit,
+    JumpToNamedScene(SceneId),
+    PushScene(SceneId),
+    PopScene,
+    ReplaceScene(SceneId),
     Stay,
 }
 
+/// A change to the scene stack, applied on next `play_scene`.
+enum StackChange {
+    /// Unloads every scene on the stack and starts again with the given one.
+    Jump(SceneId),
+    Push(SceneId),
+    Pop,
+    Replace(SceneId),
+}
+
 /// ## Scene Command
 /// Scene Command is a struct used as a return value for the `update` scene's function.
 /// It's used to send little commands that can be parsed by `SceneManager`, these commands
@@ -56,16 +234,48 @@ enum Command {
 /// another scene or, as it is, continue to execute the program on certain scene.
 pub struct SceneCommand {
     command: Command,
-    args: Vec<&'static str>
+    transition: Option<(Transition, f32)>,
+    payload: Option<ScenePayload>,
 }
 
 impl SceneCommand {
     /// Creates a simple flow command to jump to another scene by giving
-    /// the name of scene.
-    pub fn jump_to_scene(scene_name: &'static str) -> SceneCommand {
+    /// the name of scene. Every scene on the stack is unloaded.
+    pub fn jump_to_scene(scene: impl Into<SceneId>) -> SceneCommand {
+        SceneCommand {
+            command: Command::JumpToNamedScene(scene.into()),
+            transition: None,
+            payload: None,
+        }
+    }
+
+    /// Generates a flow command to show a scene over the current one, which is
+    /// paused until the new scene is popped.
+    pub fn push_scene(scene: impl Into<SceneId>) -> SceneCommand {
+        SceneCommand {
+            command: Command::PushScene(scene.into()),
+            transition: None,
+            payload: None,
+        }
+    }
+
+    /// Generates a flow command to close the current scene, going back to the
+    /// one under it.
+    pub fn pop_scene() -> SceneCommand {
         SceneCommand {
-            command: Command::JumpToNamedScene,
-            args: vec![scene_name]
+            command: Command::PopScene,
+            transition: None,
+            payload: None,
+        }
+    }
+
+    /// Generates a flow command to replace the current scene, keeping the ones
+    /// under it.
+    pub fn replace_scene(scene: impl Into<SceneId>) -> SceneCommand {
+        SceneCommand {
+            command: Command::ReplaceScene(scene.into()),
+            transition: None,
+            payload: None,
         }
     }
 
@@ -73,71 +283,508 @@ impl SceneCommand {
     pub fn continue_program() -> SceneCommand {
         SceneCommand {
             command: Command::Stay,
-            args: vec![]
+            transition: None,
+            payload: None,
         }
     }
 
     /// Generates a flow command to finish the program.
     pub fn exit_program() -> SceneCommand {
+        SceneCommand::exit_with_code(0)
+    }
+
+    /// Generates a flow command to finish the program with a status code.
+    pub fn exit_with_code(code: i32) -> SceneCommand {
+        SceneCommand {
+            command: Command::Exit(code),
+            transition: None,
+            payload: None,
+        }
+    }
+
+    /// Finishes the program without asking for confirmation, with the code of
+    /// the exit being confirmed.
+    pub(crate) fn confirm_exit() -> SceneCommand {
         SceneCommand {
-            command: Command::Exit,
-            args: vec![]
+            command: Command::ConfirmExit,
+            transition: None,
+            payload: None,
         }
     }
+
+    /// Plays `transition` for `duration` seconds when the command changes the
+    /// scene. Ignored by commands that don't.
+    pub fn with_transition(mut self, transition: Transition, duration: f32) -> SceneCommand {
+        self.transition = Some((transition, duration));
+        self
+    }
+
+    /// Hands `payload` to the scene the command brings on top, through
+    /// `AsScene::receive`. For `pop_scene`, that's the scene below.
+    pub fn with_payload<T: Any>(mut self, payload: T) -> SceneCommand {
+        self.payload = Some(ScenePayload::new(payload));
+        self
+    }
 }
 
 impl SceneManager {
     /// Creates a new and empty Scene Manager context.
     pub fn new() -> Self {
+        let mut context = AppContext::new();
+        context.insert(InputMap::default_bindings());
         Self {
             scene_list: vec![],
-            current_scene: Rc::from(RefCell::from(None))
+            stack: vec![],
+            pending: None,
+            pending_payload: None,
+            pending_transition: None,
+            transition: None,
+            targets: None,
+            context,
+            input: Input::new(),
+            requested_exit: None,
+            exit_code: None,
+            confirm_exit: false,
+            home_scene: None,
+            // The first frame always updates scenes before drawing them.
+            accumulator: FIXED_STEP,
+            latched_input: None,
+            library_revision: None,
+        }
+    }
+
+    /// Makes exits ask "are you sure?" on a dialog before going on.
+    pub fn set_confirm_exit(&mut self, confirm_exit: bool) {
+        self.confirm_exit = confirm_exit;
+        if confirm_exit && self.get_scene_by_name(EXIT_DIALOG).is_err() {
+            let _ = self.push_scene(Box::new(ExitDialog::new()));
+        }
+    }
+
+    /// Asks the program to finish with `code`, like a scene returning
+    /// `SceneCommand::exit_with_code` would. Used for window close requests.
+    pub fn request_exit(&mut self, code: i32) {
+        if !self.confirm_exit {
+            self.exit_code = Some(code);
+            return;
+        }
+
+        let asking = self.stack.iter().any(|s| s.borrow().as_ref().is_some_and(|s| s.name() == EXIT_DIALOG));
+        if !asking {
+            self.requested_exit = Some(code);
+            self.pending = Some(StackChange::Push(SceneId::from(EXIT_DIALOG)));
+            self.pending_transition = None;
+            self.pending_payload = None;
+        }
+    }
+
+    /// Code the program should exit with, once a scene asked for it.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// Unloads every scene on the stack, from top to bottom, saves what they
+    /// changed on the library and the `Config`, and lets plugins know the
+    /// program is finishing. Scenes and the `AssetCache` are dropped
+    /// too, so their textures are freed while the window is still open.
+    pub fn shutdown(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        self.pending = None;
+        while !self.stack.is_empty() {
+            self.leave_top(rl, thread);
+        }
+        self.sync_library();
+        if let Some(config) = self.context.get::<Config>() {
+            if let Err(err) = config.save() {
+                println!("{}", err);
+            }
         }
+        if let Some(plugins) = self.context.get_mut::<PluginHost>() {
+            plugins.shutdown();
+        }
+        self.scene_list.clear();
+        self.context.remove::<AssetCache>();
+        self.targets = None;
+    }
+
+    /// Services shared by scenes. A `PluginHost` put here is notified when
+    /// scenes are loaded, unloaded, paused and resumed, and sees the games of
+    /// the `GameLibrary`, which is saved as soon as scenes change it. An
+    /// `InputMap` replaces the default bindings, and an `AssetCache` is updated
+    /// every frame.
+    pub fn context_mut(&mut self) -> &mut AppContext {
+        &mut self.context
     }
 
-    /// Inserts a scene on scene's list.
-    pub fn push_scene(&mut self, scene: Box<dyn AsScene>){
+    /// Inserts a scene on scene's list. Names identify scenes, so a scene
+    /// named as another one already on the list is refused.
+    pub fn push_scene(&mut self, scene: Box<dyn AsScene>) -> Result<(), SceneError> {
+        if self.get_scene_by_name(scene.name()).is_ok() {
+            return Err(SceneError::Duplicate(String::from(scene.name())));
+        }
         self.scene_list.push(Rc::from(RefCell::from(Option::from(scene))));
+        Ok(())
     }
 
-    /// Makes a search on scene list with the given name. 
-    /// If finds it, returns a `RefCell` filled with a `Option` with scene
-    /// inside it. If don't, also returns a `RefCell` with `Option::None`
-    /// inside it.
-    pub fn get_scene_by_name(&self, scene_name: &'static str) -> Rc<RefCell<Option<Box<dyn AsScene>>>> {
-        let scene = self.scene_list.iter().find(|s| s.borrow().as_ref().unwrap().name() == scene_name).cloned();
+    /// Makes a search on scene list with the given name, returning the
+    /// `RefCell` holding the scene.
+    pub fn get_scene_by_name(&self, scene_name: &str) -> Result<SceneRef, SceneError> {
+        self.scene_list.iter()
+            .find(|s| s.borrow().as_ref().is_some_and(|s| s.name() == scene_name))
+            .cloned()
+            .ok_or_else(|| SceneError::NotFound(String::from(scene_name)))
+    }
 
-        return scene.unwrap_or(Rc::from(RefCell::from(None)));
+    /// The scene on top of the stack, the one receiving input.
+    pub fn current_scene(&self) -> Option<SceneRef> {
+        self.stack.last().cloned()
     }
 
     /// Set current scene as a scene with given name inside scene's list.
-    pub fn set_current_scene(&mut self, scene_name: &'static str) {
-        self.current_scene = self.get_scene_by_name(scene_name);
+    /// The switch happens on next `play_scene`, which unloads every scene on
+    /// the stack and loads the new one. The first scene set this way is also
+    /// where the error scene goes back to when there's nothing else.
+    pub fn set_current_scene(&mut self, scene: impl Into<SceneId>) -> Result<(), SceneError> {
+        let scene = scene.into();
+        self.get_scene_by_name(scene.as_str())?;
+        self.home_scene.get_or_insert_with(|| scene.clone());
+        self.parse_command(SceneCommand::jump_to_scene(scene));
+        Ok(())
+    }
+
+    /// Applies the change waiting on `pending`, calling the lifecycle functions
+    /// of the scenes involved and notifying plugins. When the new scene can't
+    /// be played, the error scene is shown instead.
+    fn apply_pending(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        let payload = self.pending_payload.take();
+        let result = match self.pending.take() {
+            Some(StackChange::Jump(scene)) => {
+                while !self.stack.is_empty() {
+                    self.leave_top(rl, thread);
+                }
+                self.enter(&scene, payload, rl, thread)
+            }
+            Some(StackChange::Push(scene)) => self.push(&scene, payload, rl, thread),
+            Some(StackChange::Pop) => {
+                self.pop(payload, rl, thread);
+                Ok(())
+            }
+            Some(StackChange::Replace(scene)) => {
+                self.leave_top(rl, thread);
+                self.enter(&scene, payload, rl, thread)
+            }
+            None => Ok(()),
+        };
+
+        if let Err(err) = result {
+            self.show_error(err, rl, thread);
+        }
+    }
+
+    fn push(&mut self, id: &SceneId, payload: Option<ScenePayload>, rl: &mut RaylibHandle, thread: &RaylibThread) -> Result<(), SceneError> {
+        if self.stack.iter().any(|s| s.borrow().as_ref().is_some_and(|s| s.name() == id.as_str())) {
+            return Err(SceneError::AlreadyOnStack(id.to_string()));
+        }
+        let scene = self.get_scene_by_name(id.as_str())?;
+        self.pause_top(rl, thread);
+        self.load(scene, payload, rl, thread);
+        Ok(())
+    }
+
+    /// Leaves the top scene, resuming the one below. Popping the last scene
+    /// goes back to the first scene instead.
+    fn pop(&mut self, payload: Option<ScenePayload>, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        if self.stack.len() < 2 {
+            match self.home_scene.clone() {
+                Some(home) => self.pending = Some(StackChange::Jump(home)),
+                None => println!("can't pop the last scene"),
+            }
+            self.pending_payload = payload;
+            return self.apply_pending(rl, thread);
+        }
+
+        self.leave_top(rl, thread);
+        if let Some(top) = self.stack.last().cloned() {
+            if let Some(scene) = top.borrow_mut().as_mut() {
+                if let Some(payload) = payload {
+                    scene.receive(payload);
+                }
+                scene.on_resume(rl, thread);
+                self.emit("resume", scene.name());
+            }
+        }
+    }
+
+    fn pause_top(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        if let Some(top) = self.stack.last().cloned() {
+            if let Some(scene) = top.borrow_mut().as_mut() {
+                scene.on_pause(rl, thread);
+                self.emit("pause", scene.name());
+            }
+        }
+    }
+
+    /// Loads the scene with the given id and puts it on top of the stack.
+    fn enter(&mut self, id: &SceneId, payload: Option<ScenePayload>, rl: &mut RaylibHandle, thread: &RaylibThread) -> Result<(), SceneError> {
+        let scene = self.get_scene_by_name(id.as_str())?;
+        self.load(scene, payload, rl, thread);
+        Ok(())
+    }
+
+    /// Hands `payload` to `scene`, loads it and puts it on top of the stack.
+    fn load(&mut self, scene: SceneRef, payload: Option<ScenePayload>, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        if let Some(loaded) = scene.borrow_mut().as_mut() {
+            if let Some(payload) = payload {
+                loaded.receive(payload);
+            }
+            loaded.load(rl, thread, &mut self.context);
+            self.emit("load", loaded.name());
+        }
+        self.stack.push(scene);
+    }
+
+    /// Reports `error` and pushes a new error scene telling about it.
+    fn show_error(&mut self, error: SceneError, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        println!("scene error: {}", error);
+        let scene: SceneRef = Rc::new(RefCell::new(Some(Box::new(ErrorScene::new(error.to_string())))));
+        self.pause_top(rl, thread);
+        self.load(scene, None, rl, thread);
+    }
+
+    /// Unloads the scene on top of the stack and removes it.
+    fn leave_top(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        if let Some(top) = self.stack.pop() {
+            if let Some(scene) = top.borrow_mut().as_mut() {
+                scene.unload(rl, thread, &mut self.context);
+                self.emit("unload", scene.name());
+            }
+        }
+    }
+
+    fn emit(&mut self, event: &str, scene_name: &str) {
+        if let Some(plugins) = self.context.get_mut::<PluginHost>() {
+            plugins.emit(event, scene_name);
+        }
     }
 
     /// Parses a `SceneCommand` sent by the current scene in execution.    
     pub fn parse_command(& mut self, scene_command: SceneCommand) {
+        if !matches!(scene_command.command, Command::Stay | Command::Exit(_) | Command::ConfirmExit) {
+            self.pending_transition = scene_command.transition;
+            self.pending_payload = scene_command.payload;
+        }
         match scene_command.command {
-            Command::JumpToNamedScene => { 
-                self.set_current_scene(scene_command.args[0])
+            Command::JumpToNamedScene(scene) => { 
+                self.pending = Some(StackChange::Jump(scene))
             },
-            Command::Exit => {std::process::exit(0);},
+            Command::PushScene(scene) => self.pending = Some(StackChange::Push(scene)),
+            Command::PopScene => self.pending = Some(StackChange::Pop),
+            Command::ReplaceScene(scene) => self.pending = Some(StackChange::Replace(scene)),
+            Command::Exit(code) => self.request_exit(code),
+            Command::ConfirmExit => self.exit_code = Some(self.requested_exit.unwrap_or(0)),
             Command::Stay => {}
         }
     }
 
-    /// Plays the current scene set on Scene Manager.
+    /// Updates the scene on top of the stack by `FIXED_STEP` seconds.
+    ///
+    /// A scene entered on this step gets updated right away, but the presses
+    /// that made the previous scene leave aren't seen twice. A change with a
+    /// transition waits for next frame, so the outgoing scenes can be drawn
+    /// one last time.
+    fn step(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, input: &InputState) {
+        let mut input = input.clone();
+        while let Some(scene) = self.current_scene() {
+            if self.exit_code.is_some() {
+                break;
+            }
+            let command = match scene.borrow_mut().as_mut() {
+                Some(scene) => scene.update(rl, thread, FIXED_STEP, &input, &mut self.context),
+                None => break,
+            };
+            self.parse_command(command);
+            if self.pending_transition.is_some_and(|(_, duration)| duration <= 0.0) {
+                self.pending_transition = None;
+            }
+            if self.pending.is_none() || self.pending_transition.is_some() {
+                break;
+            }
+            self.apply_pending(rl, thread);
+            input = input.without_presses();
+        }
+    }
+
+    /// Plays the current scene set on Scene Manager: updates the scene on top
+    /// of the stack and draws the visible ones.
     /// 
     /// **OBS:** As said on Scene Manager's doc, `SceneManager` is
     /// built with **Raylib-rs** backend, so you need to borrow `RaylibHandle`
     /// and `RaylibThread` for this function.
     pub fn play_scene(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
-        let scene = Rc::clone(&self.current_scene);
-        let command: SceneCommand = scene.borrow_mut().as_mut().unwrap().update(rl, thread);
-        self.parse_command(command);
+        self.start_transition();
+        self.apply_pending(rl, thread);
+
+        if self.stack.is_empty() && self.pending.is_none() && self.exit_code.is_none() {
+            self.show_error(SceneError::NothingToPlay, rl, thread);
+        }
+
+        if let Some(assets) = self.context.get_mut::<AssetCache>() {
+            assets.update(rl, thread);
+        }
+
+        let polled = match self.context.get::<InputMap>() {
+            Some(map) => self.input.poll(rl, map),
+            None => self.input.poll(rl, &InputMap::default_bindings()),
+        };
+        let mut input = match self.latched_input.take() {
+            Some(mut latched) => {
+                latched.merge(polled);
+                latched
+            }
+            None => polled,
+        };
+        if let Some((_, duration, elapsed)) = &mut self.transition {
+            *elapsed += rl.get_frame_time();
+            if *elapsed >= *duration {
+                self.transition = None;
+            } else {
+                input = input.without_presses();
+            }
+        }
+
+        // Scenes are updated in fixed steps, as many as fit in the time since
+        // last frame. Presses go to the first step; when no step fits, they're
+        // kept for the next frame.
+        self.accumulator += rl.get_frame_time().min(MAX_FRAME_TIME);
+        let mut stepped = false;
+        while self.accumulator >= FIXED_STEP {
+            self.accumulator -= FIXED_STEP;
+            self.step(rl, thread, &input);
+            input = input.without_presses();
+            stepped = true;
+            if self.exit_code.is_some() || self.pending.is_some() {
+                break;
+            }
+        }
+        if !stepped {
+            self.latched_input = Some(input);
+        }
+        self.sync_library();
+
+        if self.pending.is_some() && self.pending_transition.is_some() {
+            self.prepare_targets(rl, thread);
+        }
+        let capture = self.pending.is_some() && self.pending_transition.is_some();
+        let mut d: RaylibDrawHandle = rl.begin_drawing(thread);
+        match (&self.targets, &self.transition) {
+            (Some((outgoing, _)), _) if capture => {
+                self.draw_stack_on(&mut d, outgoing);
+                d.draw_texture_rec(
+                    outgoing.texture(),
+                    Rectangle::new(0.0, 0.0, outgoing.texture.width as f32, -outgoing.texture.height as f32),
+                    Vector2::new(0.0, 0.0),
+                    Color::WHITE
+                );
+            }
+            (Some((outgoing, incoming)), Some((transition, duration, elapsed))) => {
+                self.draw_stack_on(&mut d, incoming);
+                let progress = ease::cubic_in_out(*elapsed, 0.0, 1.0, *duration);
+                transition.draw(&mut d, outgoing, incoming, progress.clamp(0.0, 1.0));
+            }
+            _ => self.draw_stack(&mut d),
+        }
+    }
+
+    /// Saves the library when scenes changed it, so changes aren't lost if
+    /// the program is killed, and hands the games to plugins again, so scripts
+    /// don't see removed or outdated games.
+    fn sync_library(&mut self) {
+        let revision = self.context.get::<GameLibrary>().map(GameLibrary::revision);
+        if revision.is_none() || revision == self.library_revision {
+            return;
+        }
+        // The library was just opened when seen for the first time.
+        let changed = self.library_revision.is_some();
+        self.library_revision = revision;
 
-        if scene.borrow().as_ref().unwrap().name() != self.current_scene.as_ref().borrow().as_ref().unwrap().name() {
-            self.play_scene(rl, thread)
+        if let (true, Some(library)) = (changed, self.context.get::<GameLibrary>()) {
+            if let Err(err) = library.save() {
+                println!("{}", err);
+            }
+        }
+        if let Some(mut plugins) = self.context.remove::<PluginHost>() {
+            if let Some(library) = self.context.get::<GameLibrary>() {
+                plugins.set_library(library);
+            }
+            self.context.insert(plugins);
+        }
+    }
+
+    /// Starts the transition waiting on `pending_transition`, along with the
+    /// change it belongs to. The outgoing scenes were captured when the change
+    /// was asked, on previous frame.
+    fn start_transition(&mut self) {
+        if let Some((transition, duration)) = self.pending_transition.take() {
+            if self.pending.is_some() && self.targets.is_some() {
+                self.transition = Some((transition, duration, 0.0));
+            }
+        }
+    }
+
+    /// Gets render textures as big as the screen ready for a transition. Without
+    /// them, the change happens without one.
+    fn prepare_targets(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        let (width, height) = (rl.get_screen_width(), rl.get_screen_height());
+        let outdated = self.targets.as_ref()
+            .is_none_or(|(target, _)| target.texture.width != width || target.texture.height != height);
+        if !outdated {
+            return;
+        }
+
+        let outgoing = rl.load_render_texture(thread, width as u32, height as u32);
+        let incoming = rl.load_render_texture(thread, width as u32, height as u32);
+        self.targets = match (outgoing, incoming) {
+            (Ok(outgoing), Ok(incoming)) => Some((outgoing, incoming)),
+            (Err(err), _) | (_, Err(err)) => {
+                println!("can't play scene transition: {}", err);
+                self.pending_transition = None;
+                None
+            }
+        };
+    }
+
+    /// Draws the visible scenes of the stack on `target` instead of the screen.
+    ///
+    /// Texture mode is set through raylib directly because scenes draw on a
+    /// `RaylibDrawHandle`, which the guard of raylib-rs' `begin_texture_mode`
+    /// doesn't hand out.
+    fn draw_stack_on(&self, d: &mut RaylibDrawHandle, target: &RenderTexture2D) {
+        // SAFETY: `d` proves the frame was begun on the main thread and is still
+        // open, which is where raylib allows texture mode. `target` is borrowed
+        // for the whole call, so its framebuffer stays alive until
+        // `EndTextureMode`, which is always reached: scenes can't leave the
+        // frame early, they only get `d` to draw with.
+        unsafe { raylib::ffi::BeginTextureMode(**target) };
+        d.clear_background(Color::BLACK);
+        self.draw_stack(d);
+        // SAFETY: ends the texture mode begun above, on the same frame.
+        unsafe { raylib::ffi::EndTextureMode() };
+    }
+
+    /// Draws the visible scenes of the stack. Overlays let the scenes under them
+    /// show through, down to the first scene that isn't one.
+    fn draw_stack(&self, d: &mut RaylibDrawHandle) {
+        let first_visible = self.stack.iter()
+            .rposition(|s| !s.borrow().as_ref().is_some_and(|s| s.is_overlay()))
+            .unwrap_or(0);
+
+        for (index, scene) in self.stack.iter().enumerate().skip(first_visible) {
+            if index > first_visible {
+                let (width, height) = (d.get_screen_width(), d.get_screen_height());
+                d.draw_rectangle(0, 0, width, height, Color::BLACK.fade(OVERLAY_DIM));
+            }
+            if let Some(scene) = scene.borrow_mut().as_mut() {
+                scene.draw(d, &self.context);
+            }
         }
     }
 }
@@ -146,7 +793,9 @@ impl SceneManager {
 /// This trait turns any struct in a scene that can be inserted to
 /// Scene Manager. These structs can be handled in a way that they
 /// can display something on screen when they are called. There are
-/// 4 functions that need to have a implementation.
+/// 5 functions that need to have a implementation: `load`, `update`, `draw`,
+/// `unload` and `name`. The others have defaults, for scenes that care about
+/// the stack (`on_pause`, `on_resume`, `is_overlay`) or payloads (`receive`).
 /// 
 /// ### How to use it
 /// The best way to start with it is creating a struct with all your
@@ -178,12 +827,11 @@ impl SceneManager {
 /// // There you implement AsScene trait;
 /// 
 /// impl AsScene for Menu {
-///     // There I will only show update function, anyways, this scene managing 
-///     // implementation doesn't call other functions, you will need to call them
-///     // yourself.
+///     // There I will only show update function. `load` and `unload` are called
+///     // by SceneManager when entering and leaving the scene.
 /// 
-///     fn update(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) -> SceneCommand {
-///         // do all the stuff...
+///     fn update(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, dt: f32, input: &InputState, context: &mut AppContext) -> SceneCommand {
+///         // do all the stuff, drawing aside: it goes on `draw`...
 ///         match button.label { // Simple example for SceneCommand states:
 ///              // SceneCommand is needed as a return value for 
 ///              // `update` function, it  shows what Scene 
@@ -196,14 +844,46 @@ impl SceneManager {
 /// }
 /// ```
 /// 
+/// `input` holds the logical actions (`Action::Confirm`, `Action::Left`...) of the
+/// frame, already read from keyboard, gamepad and mouse by `SceneManager`. Updates
+/// run on a fixed timestep of `FIXED_STEP` seconds (`dt`), so animations should be
+/// timed in seconds rather than frames. Drawing happens on `draw`, after every
+/// update of the frame, so scenes under an
+/// overlay can still be drawn. `context` holds the services shared by scenes,
+/// see `AppContext`; on `draw` it can only be read, like to get textures from
+/// the `AssetCache`.
+///
 /// After implementing `AsScene` trait, then you can you use it with `SceneManager` and call
 /// the functions to show something on the screen. Remembering there that SceneManager uses
 /// Raylib-rs as their backend, so you need to pass both `RaylibHandle` and `RaylibThread`
 /// to functions.
 pub trait AsScene {
-    fn load(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread);
-    fn update(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) -> SceneCommand;
-    fn unload(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread);
+    /// Called when the scene becomes the current one, before its first update.
+    fn load(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, context: &mut AppContext);
+    /// Advances the scene by `dt` seconds, always `FIXED_STEP`.
+    fn update(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, dt: f32, input: &InputState, context: &mut AppContext) -> SceneCommand;
+    /// Draws the scene. Called every frame the scene is visible, even when it
+    /// isn't updated because an overlay is on top of it.
+    fn draw(&mut self, d: &mut RaylibDrawHandle, context: &AppContext);
+    /// Called when another scene replaces this one.
+    fn unload(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, context: &mut AppContext);
+
+    /// Called when another scene is shown over this one without replacing it,
+    /// like an overlay. The scene stays loaded.
+    fn on_pause(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread) {}
+    /// Called when the scene shown over this one goes away.
+    fn on_resume(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread) {}
+
+    /// Called with the payload of the command that brought the scene on top,
+    /// before `load` (or `on_resume`, when the scene over it was popped).
+    fn receive(&mut self, _payload: ScenePayload) {}
+
+    /// Overlays are drawn over the scenes under them on the stack, which are
+    /// dimmed, instead of hiding them.
+    fn is_overlay(&self) -> bool {
+        false
+    }
 
-    fn name(&self) -> &'static str;
+    /// Name identifying the scene on `SceneManager`, see `SceneId`.
+    fn name(&self) -> &str;
 }
\ No newline at end of file
@@ -0,0 +1,92 @@
+use raylib::prelude::*;
+
+/// Side the outgoing scene leaves through on a `Transition::Slide`.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum SlideDirection {
+    Left,
+    Right,
+}
+
+/// ## Transition
+/// Effect played by `SceneManager` when a `SceneCommand` changes the scene,
+/// given with `SceneCommand::with_transition`:
+///
+/// ```rust
+/// SceneCommand::jump_to_scene("Menu").with_transition(Transition::Fade(Color::BLACK), 0.6)
+/// ```
+///
+/// Scenes don't have to do anything for it: the manager keeps a picture of the
+/// outgoing scenes and draws the incoming ones on a render texture, mixing both.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Transition {
+    /// Fades the outgoing scene to a color, then the incoming one from it.
+    Fade(Color),
+    /// Blends the outgoing scene into the incoming one.
+    Crossfade,
+    /// Pushes the outgoing scene out of the screen while the incoming one
+    /// takes its place.
+    Slide(SlideDirection),
+    /// Closes black curtains from top and bottom over the outgoing scene, then
+    /// opens them on the incoming one.
+    Curtain,
+}
+
+impl Transition {
+    /// Draws the transition at `progress`, from 0.0 (only the outgoing scene)
+    /// to 1.0 (only the incoming one).
+    pub(crate) fn draw(
+        &self,
+        d: &mut RaylibDrawHandle,
+        outgoing: &RenderTexture2D,
+        incoming: &RenderTexture2D,
+        progress: f32,
+    ) {
+        let (width, height) = (d.get_screen_width() as f32, d.get_screen_height() as f32);
+        match *self {
+            Transition::Fade(color) => {
+                let (target, amount) = if progress < 0.5 {
+                    (outgoing, progress * 2.0)
+                } else {
+                    (incoming, (1.0 - progress) * 2.0)
+                };
+                draw_target(d, target, Vector2::new(0.0, 0.0), 1.0);
+                d.draw_rectangle(0, 0, width as i32, height as i32, color.fade(amount));
+            }
+            Transition::Crossfade => {
+                draw_target(d, outgoing, Vector2::new(0.0, 0.0), 1.0);
+                draw_target(d, incoming, Vector2::new(0.0, 0.0), progress);
+            }
+            Transition::Slide(direction) => {
+                let step = match direction {
+                    SlideDirection::Left => Vector2::new(-width, 0.0),
+                    SlideDirection::Right => Vector2::new(width, 0.0),
+                };
+                draw_target(d, outgoing, step.scale_by(progress), 1.0);
+                draw_target(d, incoming, step.scale_by(progress - 1.0), 1.0);
+            }
+            Transition::Curtain => {
+                let (target, closed) = if progress < 0.5 {
+                    (outgoing, progress * 2.0)
+                } else {
+                    (incoming, (1.0 - progress) * 2.0)
+                };
+                draw_target(d, target, Vector2::new(0.0, 0.0), 1.0);
+                let curtain = (height / 2.0 * closed).ceil() as i32;
+                d.draw_rectangle(0, 0, width as i32, curtain, Color::BLACK);
+                d.draw_rectangle(0, height as i32 - curtain, width as i32, curtain, Color::BLACK);
+            }
+        }
+    }
+}
+
+/// Draws a render texture at `position`. Render textures are stored upside
+/// down, so the source is flipped.
+fn draw_target(d: &mut RaylibDrawHandle, target: &RenderTexture2D, position: Vector2, alpha: f32) {
+    let (width, height) = (target.texture.width as f32, target.texture.height as f32);
+    d.draw_texture_rec(
+        target.texture(),
+        Rectangle::new(0.0, 0.0, width, -height),
+        position,
+        Color::WHITE.fade(alpha)
+    );
+}
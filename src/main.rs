@@ -1,35 +1,156 @@
-use raylib::prelude::*;
-
 mod elements;
 mod scenes;
-use crate::elements::{Button, ButtonStyle, Cover, SceneManager};
-
-use std::cell::RefCell;
-use std::rc::Rc;
+use crate::elements::{AssetCache, Config, EmulatorRegistry, GameLibrary, PlaceholderCovers, PluginHost, SceneManager, scan_library};
 
 fn main() {
+    let code = run();
+    std::process::exit(code);
+}
+
+/// Runs Starframe until it's asked to exit, returning the exit code. Everything
+/// is dropped before returning, so nothing is left behind by `process::exit`.
+fn run() -> i32 {
     println!("starting...");
     let mut scene_manager = SceneManager::new();
-    let (mut rl, thread) = raylib::init()
-        .size(640, 480)
-        .title("Starframe")
-        .resizable()
-        .build();
+    // Like the library, a config that failed to load is kept untouched on disk.
+    let mut config = Config::open_default().unwrap_or_else(|err| {
+        println!("{}, starting with default settings", err);
+        let mut config = Config::new(Config::default_path());
+        config.set_read_only(true);
+        config
+    });
+    for problem in config.repair() {
+        println!("{}, using its default", problem);
+    }
+    if config.is_outdated() {
+        if let Err(err) = config.save() {
+            println!("{}", err);
+        }
+    }
+    let mut emulators = EmulatorRegistry::open_default().unwrap_or_else(|err| {
+        println!("{}, starting without emulators", err);
+        EmulatorRegistry::new(EmulatorRegistry::default_path())
+    });
+    let (mut library, library_loaded) = match GameLibrary::open_default() {
+        Ok(library) => (library, true),
+        Err(err) => {
+            println!("{}, starting with an empty library", err);
+            let mut library = GameLibrary::new(GameLibrary::default_path());
+            library.set_read_only(true);
+            (library, false)
+        }
+    };
+
+    let mut plugins = PluginHost::new(PluginHost::default_directory());
+    plugins.set_library(&library);
+    plugins.set_disabled(config.plugins.disabled.clone());
+    plugins.load_all();
+    for profile in plugins.take_emulators() {
+        if let Err(err) = emulators.add(profile) {
+            println!("{}", err);
+        }
+    }
+
+    // A library that failed to load is kept untouched on disk, so it isn't
+    // scanned, and it's read-only so scenes don't save over it.
+    if library_loaded && config.library.scan_on_startup {
+        let report = scan_library(&mut library, &emulators);
+        for error in &report.errors {
+            println!("scan: {}", error);
+        }
+        for moved in &report.moved {
+            println!("scan: game {} moved from {} to {}", moved.id, moved.from.display(), moved.to.display());
+        }
+        if report.has_changes() {
+            println!("library scan: {}", report);
+            if let Err(err) = library.save() {
+                println!("{}", err);
+            }
+            plugins.set_library(&library);
+        }
+    }
+
+    // Services shared by every scene.
+    let context = scene_manager.context_mut();
+    context.insert(library);
+    context.insert(emulators);
+    context.insert(plugins);
+    context.insert(PlaceholderCovers::new(PlaceholderCovers::default_path()));
+    context.insert(config.input.input_map());
+    let window = config.window.clone();
+    let library_settings = config.library.clone();
+    context.insert(config);
+
+    let mut builder = raylib::init();
+    builder.size(window.width, window.height)
+        .title(&window.title);
+    if window.resizable {
+        builder.resizable();
+    }
+    if window.vsync {
+        builder.vsync();
+    }
+    let (mut rl, thread) = builder.build();
+    if window.fullscreen {
+        rl.toggle_fullscreen();
+    }
+    rl.set_target_fps(window.fps);
+
+    // Textures need the window, so the cache comes after it.
+    match AssetCache::new(&mut rl, &thread) {
+        Ok(assets) => {
+            scene_manager.context_mut().insert(assets);
+        }
+        Err(err) => println!("can't create asset cache: {}", err),
+    }
 
     // START OF SCENES BUILDING -------------------------
 
-    let mut screen_0 = scenes::Opening::new(&mut rl, &thread);
-    scene_manager.push_scene(Box::new(screen_0));
+    let screen_0 = scenes::Opening::new(&mut rl, &thread);
+    if let Err(err) = scene_manager.push_scene(Box::new(screen_0)) {
+        println!("{}", err);
+    }
 
     // END OF SCENE 0 BUILDING --------------------------
 
-    let mut screen_1 = scenes::MainScreen::new(&mut rl, &thread);
-    scene_manager.push_scene(Box::new(screen_1));
+    let screen_1 = scenes::MainScreen::new(&mut rl, &thread, &library_settings);
+    if let Err(err) = scene_manager.push_scene(Box::new(screen_1)) {
+        println!("{}", err);
+    }
     
     // END OF SCENE 1 BUILDING --------------------------
 
-    scene_manager.set_current_scene("Opening");
-    while !rl.window_should_close() {
-        scene_manager.play_scene(&mut rl, &thread);
+    let screen_2 = scenes::GameDetails::new();
+    if let Err(err) = scene_manager.push_scene(Box::new(screen_2)) {
+        println!("{}", err);
+    }
+
+    // END OF SCENE 2 BUILDING --------------------------
+
+    let screen_3 = scenes::Options::new();
+    if let Err(err) = scene_manager.push_scene(Box::new(screen_3)) {
+        println!("{}", err);
     }
+
+    // END OF SCENE 3 BUILDING --------------------------
+
+    // Escape closes dialogs, not the whole station; closing the window asks first.
+    rl.set_exit_key(None);
+    scene_manager.set_confirm_exit(true);
+    if let Err(err) = scene_manager.set_current_scene("Opening") {
+        println!("{}", err);
+    }
+    let code = loop {
+        if rl.window_should_close() {
+            scene_manager.request_exit(0);
+        }
+        if let Some(code) = scene_manager.exit_code() {
+            break code;
+        }
+        scene_manager.play_scene(&mut rl, &thread);
+    };
+
+    println!("closing...");
+    scene_manager.shutdown(&mut rl, &thread);
+    code
 }
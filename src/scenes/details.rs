@@ -0,0 +1,409 @@
+use crate::elements::library::unix_now;
+use crate::elements::placeholders::wrap_text;
+use crate::elements::{Action, AppContext, AssetCache, AsScene, Button, ButtonEvent, ButtonStyle, Config, CoverFit, FocusGroup, GameEntry, GameLibrary, InputState, PlaceholderCovers, SceneCommand, ScenePayload, SlideDirection, TextureHandle, Theme, Transition};
+use raylib::prelude::*;
+
+/// Name the details scene is registered with.
+pub const GAME_DETAILS: &str = "Game Details";
+
+/// Payload opening `GameDetails` on the game with this library id.
+pub struct GameSelected(pub u64);
+
+/// Payload handed back to the scene below when `GameDetails` is closed.
+pub struct DetailsClosed {
+    pub game_id: u64,
+    /// Play was pressed: the scene below should launch the game.
+    pub play: bool,
+    /// The game was edited or removed, so views of the library are outdated.
+    pub library_changed: bool,
+}
+
+const TEXT_SIZE: i32 = 20;
+const LABEL_SIZE: i32 = 10;
+
+/// Library data that can be edited on the page, in the order it's shown.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Title,
+    Platform,
+    /// Comma separated.
+    Tags,
+    Description,
+}
+
+impl Field {
+    const ALL: [Field; 4] = [Field::Title, Field::Platform, Field::Tags, Field::Description];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Field::Title => "Title",
+            Field::Platform => "Platform",
+            Field::Tags => "Tags",
+            Field::Description => "Description",
+        }
+    }
+}
+
+/// Text typed for each `Field` while editing, indexed like `Field::ALL`.
+struct Editor {
+    field: usize,
+    values: [String; 4],
+}
+
+impl Editor {
+    fn new(game: &GameEntry) -> Editor {
+        Editor {
+            field: 0,
+            values: [game.title.clone(), game.platform.clone(), game.tags.join(", "), game.description.clone()],
+        }
+    }
+
+    /// Writes the typed values on `game`. A blank title or platform keeps the
+    /// previous one.
+    fn apply(self, game: &mut GameEntry) {
+        let [title, platform, tags, description] = self.values;
+        if !title.trim().is_empty() {
+            game.title = title.trim().to_string();
+        }
+        if !platform.trim().is_empty() {
+            game.platform = platform.trim().to_string();
+        }
+        game.tags = tags.split(',').map(str::trim).filter(|tag| !tag.is_empty()).map(String::from).collect();
+        game.description = description.trim().to_string();
+    }
+}
+
+/// Details page of a single game, with its art, library data and actions to
+/// play, favorite, edit or remove it. Push it with a `GameSelected` payload:
+///
+/// ```rust
+/// SceneCommand::push_scene(GAME_DETAILS).with_payload(GameSelected(game.id))
+/// ```
+///
+/// Playing is left to the scene below, so `DetailsClosed` is handed back when
+/// the page closes.
+pub struct GameDetails {
+    game_id: u64,
+    /// Copy of the library entry, refreshed after every change.
+    game: Option<GameEntry>,
+    art: Option<TextureHandle>,
+    library_changed: bool,
+    /// Values being typed, while editing.
+    editing: Option<Editor>,
+    /// Remove was pressed once, and waits for a second press.
+    confirming_removal: bool,
+    buttons: [Button; 4],
+    focus: FocusGroup,
+}
+
+impl GameDetails {
+    pub fn new() -> GameDetails {
+        let size = Vector2::new(110.0, 40.0);
+        GameDetails {
+            game_id: 0,
+            game: None,
+            art: None,
+            library_changed: false,
+            editing: None,
+            confirming_removal: false,
+            buttons: [
+                Button::new(Vector2::new(0.0, 0.0), size, "Play", ButtonStyle::build_default_style()),
+                Button::new(Vector2::new(0.0, 0.0), size, "Favorite", ButtonStyle::build_default_style()),
+                Button::new(Vector2::new(0.0, 0.0), size, "Edit", ButtonStyle::build_default_style()),
+                Button::new(Vector2::new(0.0, 0.0), size, "Remove", ButtonStyle::build_default_style()),
+            ],
+            focus: FocusGroup::new(),
+        }
+    }
+
+    /// Goes back to the scene below, letting it know what happened here.
+    fn close(&self, play: bool) -> SceneCommand {
+        SceneCommand::pop_scene()
+            .with_payload(DetailsClosed {
+                game_id: self.game_id,
+                play,
+                library_changed: self.library_changed,
+            })
+            .with_transition(Transition::Slide(SlideDirection::Right), 0.4)
+    }
+
+    /// Applies `edit` to the game on the library, keeping the copy shown here
+    /// up to date.
+    fn edit_game<F: FnOnce(&mut GameEntry)>(&mut self, context: &mut AppContext, edit: F) {
+        let library = match context.get_mut::<GameLibrary>() {
+            Some(library) => library,
+            None => return,
+        };
+        match library.update(self.game_id, edit) {
+            Ok(()) => {
+                self.game = library.get(self.game_id).cloned();
+                self.library_changed = true;
+            }
+            Err(err) => println!("can't edit game: {}", err),
+        }
+    }
+
+    /// Runs what a clicked button does. Returns a command when the scene must
+    /// stop there.
+    fn activate(&mut self, action: &str, context: &mut AppContext) -> Option<SceneCommand> {
+        if action != "remove" {
+            self.confirming_removal = false;
+        }
+        match action {
+            "play" => return Some(self.close(true)),
+            "favorite" => self.edit_game(context, |game| game.favorite = !game.favorite),
+            "edit" => self.editing = self.game.as_ref().map(Editor::new),
+            "remove" if !self.confirming_removal => self.confirming_removal = true,
+            "remove" => {
+                if let Some(library) = context.get_mut::<GameLibrary>() {
+                    match library.remove(self.game_id) {
+                        Ok(_) => self.library_changed = true,
+                        Err(err) => println!("can't remove game: {}", err),
+                    }
+                }
+                return Some(self.close(false));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Reads typed text into the field being edited. Tab or Up/Down switch
+    /// fields, Enter saves them all and Escape cancels. Input actions are
+    /// ignored meanwhile, since they're bound to keys used for typing.
+    fn update_editing(&mut self, input: &InputState, context: &mut AppContext) {
+        let mut editor = match self.editing.take() {
+            Some(editor) => editor,
+            None => return,
+        };
+
+        editor.values[editor.field].push_str(&input.typed);
+        let mut save = None;
+        for key in &input.keys {
+            match key {
+                KeyboardKey::KEY_BACKSPACE => {
+                    editor.values[editor.field].pop();
+                }
+                KeyboardKey::KEY_TAB | KeyboardKey::KEY_DOWN => editor.field = (editor.field + 1) % Field::ALL.len(),
+                KeyboardKey::KEY_UP => editor.field = (editor.field + Field::ALL.len() - 1) % Field::ALL.len(),
+                KeyboardKey::KEY_ENTER => save = Some(true),
+                KeyboardKey::KEY_ESCAPE => save = Some(false),
+                _ => {}
+            }
+        }
+
+        match save {
+            Some(true) => self.edit_game(context, |game| editor.apply(game)),
+            Some(false) => {}
+            None => self.editing = Some(editor),
+        }
+    }
+
+    /// Draws every editable field, the one being typed on highlighted.
+    fn draw_editor(&self, d: &mut RaylibDrawHandle, editor: &Editor, theme: Theme, area: Rectangle) {
+        let (x, mut y) = (area.x as i32, area.y as i32);
+        for (index, field) in Field::ALL.iter().enumerate() {
+            d.draw_text(field.label(), x, y, LABEL_SIZE, theme.faded_text());
+            y += LABEL_SIZE + 6;
+
+            let value = &editor.values[index];
+            let text = if index == editor.field {
+                d.draw_rectangle(x - 4, y - 4, area.width as i32, TEXT_SIZE + 8, theme.panel());
+                format!("{}_", value)
+            } else {
+                value.clone()
+            };
+            d.draw_text(tail_to_width(&text, TEXT_SIZE, area.width as i32 - 8), x, y, TEXT_SIZE, theme.text());
+            y += TEXT_SIZE + 16;
+        }
+    }
+
+    fn draw_info(&self, d: &mut RaylibDrawHandle, game: &GameEntry, theme: Theme, area: Rectangle) {
+        let (x, mut y) = (area.x as i32, area.y as i32);
+        d.draw_text(&game.title, x, y, 30, theme.text());
+        y += 40;
+
+        let favorite = if game.favorite { "  *  Favorite" } else { "" };
+        d.draw_text(&format!("{}{}", game.platform.to_uppercase(), favorite), x, y, TEXT_SIZE, Color::DARKBLUE);
+        y += 30;
+
+        let play_time = format!(
+            "Played {}h {:02}m",
+            game.play_time / 3600,
+            game.play_time / 60 % 60
+        );
+        d.draw_text(&play_time, x, y, TEXT_SIZE, theme.faded_text());
+        y += 25;
+        d.draw_text(&format!("Last played {}", last_played(game.last_played)), x, y, TEXT_SIZE, theme.faded_text());
+        y += 35;
+
+        if !game.tags.is_empty() {
+            d.draw_text(&game.tags.join(", "), x, y, TEXT_SIZE, Color::GRAY);
+            y += 35;
+        }
+
+        let description = if game.description.is_empty() { "No description." } else { &game.description };
+        for line in wrap_text(description, TEXT_SIZE, area.width as i32) {
+            if y + TEXT_SIZE > (area.y + area.height) as i32 {
+                break;
+            }
+            d.draw_text(&line, x, y, TEXT_SIZE, theme.text());
+            y += TEXT_SIZE + 4;
+        }
+    }
+}
+
+impl Default for GameDetails {
+    fn default() -> Self {
+        GameDetails::new()
+    }
+}
+
+/// End of `text` that fits `width` pixels, so the end of long fields, where
+/// typing happens, stays visible.
+fn tail_to_width(text: &str, font_size: i32, width: i32) -> &str {
+    let mut tail = text;
+    while measure_text(tail, font_size) > width {
+        match tail.char_indices().nth(1) {
+            Some((next, _)) => tail = &tail[next..],
+            None => break,
+        }
+    }
+    tail
+}
+
+/// When a game was last played, like "3 days ago".
+fn last_played(time: Option<u64>) -> String {
+    let elapsed = match time {
+        Some(time) => unix_now().saturating_sub(time),
+        None => return String::from("never"),
+    };
+    match elapsed / 86400 {
+        0 if elapsed < 3600 => String::from("just now"),
+        0 => format!("{} hours ago", elapsed / 3600),
+        1 => String::from("yesterday"),
+        days => format!("{} days ago", days),
+    }
+}
+
+impl AsScene for GameDetails {
+    fn name(&self) -> &'static str {
+        GAME_DETAILS
+    }
+
+    fn receive(&mut self, payload: ScenePayload) {
+        if let Some(GameSelected(id)) = payload.get::<GameSelected>() {
+            self.game_id = *id;
+        }
+    }
+
+    fn load(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread, context: &mut AppContext) {
+        self.game = context.get::<GameLibrary>().and_then(|library| library.get(self.game_id).cloned());
+        self.library_changed = false;
+        self.editing = None;
+        self.confirming_removal = false;
+        self.buttons[3].label = String::from("Remove");
+
+        let art = match (&self.game, context.get::<PlaceholderCovers>()) {
+            (Some(game), _) if game.cover.is_some() => game.cover.clone(),
+            (Some(game), Some(placeholders)) => placeholders.cover_for(game).ok(),
+            _ => None,
+        };
+        if let (Some(path), Some(assets)) = (art, context.get_mut::<AssetCache>()) {
+            self.art = Some(assets.acquire(&path));
+        }
+
+        self.focus.set_areas(self.buttons.iter().map(Button::bounds).collect());
+        self.focus.focus(0);
+    }
+
+    fn update(&mut self, rl: &mut RaylibHandle, _thread: &RaylibThread, dt: f32, input: &InputState, context: &mut AppContext) -> SceneCommand {
+        if self.game.is_none() {
+            return self.close(false);
+        }
+        if self.editing.is_some() {
+            self.update_editing(input, context);
+            return SceneCommand::continue_program();
+        }
+
+        let (s_wid, s_heig) = (rl.get_screen_width() as f32, rl.get_screen_height() as f32);
+        for (index, button) in self.buttons.iter_mut().enumerate() {
+            button.position = Vector2::new(
+                s_wid / 2.0 + 10.0 + (index % 2) as f32 * (button.size.x + 10.0),
+                s_heig - 110.0 + (index / 2) as f32 * (button.size.y + 10.0)
+            );
+        }
+
+        self.focus.set_areas(self.buttons.iter().map(Button::bounds).collect());
+        if input.mouse_moved {
+            self.focus.clear();
+        }
+        if let Some(direction) = input.direction() {
+            self.focus.navigate(direction);
+        }
+        if input.pressed(Action::Back) {
+            return self.close(false);
+        }
+
+        let focused = self.focus.focused();
+        let mut clicked = vec![];
+        for (index, button) in self.buttons.iter_mut().enumerate() {
+            let events = button.update(input, focused == Some(index));
+            button.animate(dt);
+            if events.contains(&ButtonEvent::Clicked) {
+                clicked.push(button.action.clone());
+            }
+        }
+        for action in clicked {
+            if let Some(command) = self.activate(&action, context) {
+                return command;
+            }
+        }
+
+        let favorite = self.game.as_ref().is_some_and(|game| game.favorite);
+        self.buttons[1].label = String::from(if favorite { "Unfavorite" } else { "Favorite" });
+        self.buttons[3].label = String::from(if self.confirming_removal { "Sure?" } else { "Remove" });
+
+        SceneCommand::continue_program()
+    }
+
+    fn draw(&mut self, d: &mut RaylibDrawHandle, context: &AppContext) {
+        let (s_wid, s_heig) = (d.get_screen_width() as f32, d.get_screen_height() as f32);
+        let theme = context.get::<Config>().map(|config| config.theme).unwrap_or_default();
+        d.clear_background(theme.background());
+
+        let game = match &self.game {
+            Some(game) => game,
+            None => return,
+        };
+
+        // Art on the left half, everything else on the right one.
+        let art_area = Rectangle::new(20.0, 20.0, s_wid / 2.0 - 30.0, s_heig - 40.0);
+        if let (Some(art), Some(assets)) = (&self.art, context.get::<AssetCache>()) {
+            let texture = assets.get(art);
+            let image_size = Vector2::new(texture.width as f32, texture.height as f32);
+            let (source, dest) = CoverFit::Contain.layout(image_size, art_area);
+            d.draw_texture_pro(texture, source, dest, Vector2::new(0.0, 0.0), 0.0, Color::WHITE);
+        }
+        let info_area = Rectangle::new(s_wid / 2.0 + 10.0, 24.0, s_wid / 2.0 - 30.0, s_heig - 150.0);
+        match &self.editing {
+            Some(editor) => self.draw_editor(d, editor, theme, info_area),
+            None => self.draw_info(d, game, theme, info_area),
+        }
+
+        for button in &self.buttons {
+            button.draw(d);
+        }
+        if self.editing.is_some() {
+            let hint = "Tab switches field, Enter saves, Escape cancels";
+            d.draw_text(hint, (s_wid / 2.0) as i32 + 10, s_heig as i32 - 130, 10, Color::GRAY);
+        }
+    }
+
+    fn unload(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread, context: &mut AppContext) {
+        if let (Some(art), Some(assets)) = (self.art.take(), context.get_mut::<AssetCache>()) {
+            assets.release(art);
+        }
+        self.game = None;
+    }
+}
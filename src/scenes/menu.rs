@@ -1,34 +1,69 @@
-use crate::elements::{AsScene, Cover, Button, ButtonStyle, SceneCommand};
+use crate::elements::{Action, AppContext, AssetCache, AsScene, Config, Cover, CoverBook, CoverFit, CoverGrid, Button, ButtonEvent, ButtonStyle, EmulatorRegistry, FocusGroup, GameLibrary, InputState, Launcher, LibraryConfig, PlaceholderCovers, PluginHost, SceneCommand, ScenePayload, SlideDirection, Transition, WindowConfig};
+use crate::elements::launcher::ExitReport;
+use crate::scenes::details::{DetailsClosed, GameSelected, GAME_DETAILS};
+use crate::scenes::options::{OptionsClosed, OPTIONS};
 use raylib::prelude::*;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+/// How the library is shown on the menu. Menu action (Tab) switches them.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LibraryView {
+    /// One big cover at a time, paged sideways.
+    Single,
+    /// Rows of thumbnails.
+    Grid,
+}
+
+/// Placeholder covers drawn on a single update at most.
+const PLACEHOLDERS_PER_UPDATE: usize = 2;
+/// Seconds each status message stays on the bottom bar.
+const STATUS_SECONDS: f64 = 5.0;
+/// Messages waiting for the bar beyond this are only logged.
+const MAX_QUEUED_STATUS: usize = 8;
 
 /// Menu screen. It contains some buttons and animation elements.
 pub struct MainScreen {
     name: &'static str,
 
-    simple_cover: Cover,
+    covers: CoverBook,
+    grid: CoverGrid,
+    view: LibraryView,
+    /// What the details page reported when closed, handled on next update.
+    closed_details: Option<DetailsClosed>,
+    /// The library changed on the Options scene.
+    reload_covers: bool,
+    /// Games whose placeholder cover isn't drawn yet.
+    pending_placeholders: Vec<u64>,
+    /// Fit of the covers, following the `library.cover_fit` setting.
+    cover_fit: CoverFit,
+    launcher: Launcher,
+    status: Option<(String, f64)>,
+    /// Messages shown after the current one, in order.
+    queued_status: VecDeque<String>,
     buttons: [Button; 5],
-    start_time: f32,
-    end_time: f32,
-    deltatime: f32,
-
-    frame_counter: i32,
-    state: i32,
-    opening_rectangle: Vec<Rectangle>
+    action_buttons: Vec<Button>,
+    focus: FocusGroup,
 }
 
 impl MainScreen {
-    /// Create "menu" scene.    
-    pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> MainScreen {
+    /// Create "menu" scene, starting on the view picked by `settings`. Covers
+    /// and plugin actions are added when the scene is loaded, from the library
+    /// and plugin host on `AppContext`.
+    pub fn new(_rl: &mut RaylibHandle, _thread: &RaylibThread, settings: &LibraryConfig) -> MainScreen {
         MainScreen {
             name: "Menu",
 
-            simple_cover: Cover::new(
-                rl.load_texture(&thread, "resources/107813.png").unwrap(), 
-                String::from("Paper Mario 64"),
-                Vector2::new(0.0, 0.0), 
-                Vector2::new(20.0, 80.0), 
-                Vector2::new(0.0, 0.0)
-            ),
+            covers: CoverBook::new(),
+            grid: CoverGrid::new(settings.grid_columns),
+            view: if settings.grid_view { LibraryView::Grid } else { LibraryView::Single },
+            closed_details: None,
+            reload_covers: false,
+            pending_placeholders: vec![],
+            cover_fit: settings.cover_fit,
+            launcher: Launcher::new(),
+            status: None,
+            queued_status: VecDeque::new(),
             buttons: [
                 Button::new(  // Start
                     Vector2::new(10.0, 10.0),
@@ -54,36 +89,234 @@ impl MainScreen {
                     Vector2::new(20.0, 80.0),
                     "<",
                     ButtonStyle::build_default_style()
-                ),
+                ).with_action("previous"),
                 Button::new(  // Go Right
                     Vector2::new(620.0, 240.0),
                     Vector2::new(20.0, 80.0),
                     ">",
                     ButtonStyle::build_default_style()
-                ),
+                ).with_action("next"),
             
             ],
-            start_time: 0.0,
-            end_time: 0.0,
-            deltatime: 0.0,
-
-            frame_counter: 0,
-            state: 0,
-            opening_rectangle: vec![
-                Rectangle::new(
-                    0.0, 
-                    0.0, 
-                    rl.get_screen_width() as f32, 
-                    (rl.get_screen_height() / 2) as f32
-                ), // Upside
-                Rectangle::new(
-                    0.0, 
-                    (rl.get_screen_height() / 2) as f32, 
-                    rl.get_screen_width() as f32, 
-                    (rl.get_screen_height() / 2) as f32), // Downside
-            ]
+            focus: MainScreen::button_focus(),
+            action_buttons: vec![],
+        }
+    }
+
+    /// Focus of the buttons above. The arrows are closer to the top buttons
+    /// than to each other, so they're linked across the cover.
+    fn button_focus() -> FocusGroup {
+        let mut focus = FocusGroup::new();
+        focus.link(3, Action::Right, 4).link(4, Action::Left, 3);
+        focus
+    }
+
+    /// Builds a cover for every game of the library, whose artwork loads in
+    /// the background. Games without artwork get a placeholder cover, drawn a
+    /// few at a time by `draw_placeholders`; games are skipped when there's no
+    /// `PlaceholderCovers` service.
+    fn load_covers(&mut self, context: &mut AppContext) {
+        let placeholders = context.get::<PlaceholderCovers>();
+        // Cover path of each game, and whether it must be drawn first.
+        let games: Vec<(u64, String, PathBuf, bool)> = match context.get::<GameLibrary>() {
+            Some(library) => library.games().iter()
+                .filter_map(|game| {
+                    let (path, pending) = match (&game.cover, placeholders) {
+                        (Some(path), _) => (path.clone(), false),
+                        (None, Some(placeholders)) => (placeholders.path_for(game), !placeholders.is_cached(game)),
+                        (None, None) => return None,
+                    };
+                    Some((game.id, game.title.clone(), path, pending))
+                })
+                .collect(),
+            None => return,
+        };
+        let assets = match context.get_mut::<AssetCache>() {
+            Some(assets) => assets,
+            None => return,
+        };
+
+        self.covers.clear(assets);
+        self.pending_placeholders.clear();
+        for (id, title, path, pending) in games {
+            let handle = if pending { assets.acquire_unloaded(&path) } else { assets.acquire(&path) };
+            let mut cover = Cover::new(
+                handle,
+                title,
+                Vector2::new(20.0, 80.0),
+                Vector2::new(0.0, 0.0)
+            );
+            cover.game_id = Some(id);
+            cover.fit = self.cover_fit;
+            if pending {
+                self.pending_placeholders.push(id);
+            }
+            self.covers.insert_cover(cover);
+        }
+    }
+
+    /// Draws up to `PLACEHOLDERS_PER_UPDATE` of the placeholder covers still
+    /// missing, nearest to the selected cover first, so the visible ones are
+    /// ready before the rest.
+    fn draw_placeholders(&mut self, context: &mut AppContext) {
+        if self.pending_placeholders.is_empty() {
+            return;
+        }
+        let selected = self.covers.selected();
+        let position_of = |id: u64| self.covers.covers.iter().position(|cover| cover.game_id == Some(id));
+        self.pending_placeholders.sort_by_key(|id| position_of(*id).map_or(usize::MAX, |index| index.abs_diff(selected)));
+
+        let count = self.pending_placeholders.len().min(PLACEHOLDERS_PER_UPDATE);
+        let drawn: Vec<(u64, bool)> = match (context.get::<PlaceholderCovers>(), context.get::<GameLibrary>()) {
+            (Some(placeholders), Some(library)) => self.pending_placeholders.drain(..count)
+                .filter_map(|id| library.get(id))
+                .map(|game| match placeholders.cover_for(game) {
+                    Ok(_) => (game.id, true),
+                    Err(err) => {
+                        println!("can't draw cover of \"{}\": {}", game.title, err);
+                        (game.id, false)
+                    }
+                })
+                .collect(),
+            _ => return,
+        };
+
+        let assets = match context.get_mut::<AssetCache>() {
+            Some(assets) => assets,
+            None => return,
+        };
+        for (id, _) in drawn.iter().filter(|(_, ok)| *ok) {
+            if let Some(cover) = self.covers.covers.iter().find(|cover| cover.game_id == Some(*id)) {
+                assets.reload(&cover.img);
+            }
+        }
+    }
+
+    /// Area between the buttons where the grid is shown.
+    fn grid_area(&self, screen_size: Vector2) -> Rectangle {
+        let bottom = if self.action_buttons.is_empty() { 0.0 } else { 40.0 };
+        Rectangle::new(0.0, 83.0, screen_size.x, screen_size.y - 83.0 - bottom)
+    }
+
+    /// Switches between the single cover and the grid, keeping the selection.
+    fn toggle_view(&mut self) {
+        self.view = match self.view {
+            LibraryView::Single => LibraryView::Grid,
+            LibraryView::Grid => LibraryView::Single,
+        };
+        self.covers.select(self.covers.selected());
+    }
+
+    /// Runs what a clicked button does, given its action id. Returns a command
+    /// when the scene must stop there.
+    fn activate(&mut self, action: &str, rl: &mut RaylibHandle, context: &mut AppContext) -> Option<SceneCommand> {
+        match action {
+            "start" => self.start_selected_game(rl, context),
+            "options" => return Some(SceneCommand::push_scene(OPTIONS).with_transition(Transition::Fade(Color::BLACK), 0.5)),
+            "exit" => return Some(SceneCommand::exit_program()),
+            "previous" => self.covers.previous(),
+            "next" => self.covers.next(),
+            action => {
+                let plugin_action = action.strip_prefix("plugin:").and_then(|index| index.parse().ok());
+                let plugins = context.get_mut::<PluginHost>();
+                if let (Some(index), Some(plugins)) = (plugin_action, plugins) {
+                    plugins.run_action(index);
+                }
+            }
+        }
+        None
+    }
+
+    /// Launches the game behind the selected cover, through its emulator
+    /// profile if it has one. Starframe window is minimized
+    /// and throttled while the game runs.
+    fn start_selected_game(&mut self, rl: &mut RaylibHandle, context: &AppContext) {
+        let game_id = self.covers.selected_cover().and_then(|c| c.game_id);
+        let game = match (game_id, context.get::<GameLibrary>()) {
+            (Some(id), Some(library)) => match library.get(id) {
+                Some(game) => game,
+                None => return,
+            },
+            _ => return,
+        };
+        let no_emulators = EmulatorRegistry::new(EmulatorRegistry::default_path());
+        let emulators = context.get::<EmulatorRegistry>().unwrap_or(&no_emulators);
+
+        match self.launcher.launch_game(game, emulators) {
+            Ok(()) => {
+                rl.set_target_fps(10);
+                // Minimized windows wait for events unless told to always run,
+                // and the launcher must keep being polled.
+                unsafe {
+                    raylib::ffi::SetWindowState(raylib::ffi::ConfigFlag::FLAG_WINDOW_ALWAYS_RUN as u32);
+                    raylib::ffi::MinimizeWindow();
+                }
+            }
+            Err(err) => self.show_status(err.to_string(), rl.get_time()),
+        }
+    }
+
+    /// Replaces the menu while a game is running, waiting for it to end. The
+    /// session is added to the game's play time.
+    fn update_while_playing(&mut self, rl: &mut RaylibHandle, context: &mut AppContext) -> SceneCommand {
+        if let Some(report) = self.launcher.poll() {
+            unsafe {
+                raylib::ffi::RestoreWindow();
+                raylib::ffi::ClearWindowState(raylib::ffi::ConfigFlag::FLAG_WINDOW_ALWAYS_RUN as u32);
+            }
+            let fps = context.get::<Config>().map(|config| config.window.fps).unwrap_or(WindowConfig::default().fps);
+            rl.set_target_fps(fps);
+            self.show_status(report.to_string(), rl.get_time());
+            record_session(&report, context);
+        }
+        SceneCommand::continue_program()
+    }
+
+    /// Opens the details page of the selected cover's game.
+    fn open_details(&self) -> Option<SceneCommand> {
+        let game_id = self.covers.selected_cover()?.game_id?;
+        Some(
+            SceneCommand::push_scene(GAME_DETAILS)
+                .with_payload(GameSelected(game_id))
+                .with_transition(Transition::Slide(SlideDirection::Left), 0.4)
+        )
+    }
+
+    /// Catches up with what happened on the details page: covers are rebuilt
+    /// if the library changed, and the game is launched if asked.
+    fn details_closed(&mut self, closed: DetailsClosed, rl: &mut RaylibHandle, context: &mut AppContext) {
+        if closed.library_changed {
+            self.load_covers(context);
+        }
+        let index = self.covers.covers.iter().position(|cover| cover.game_id == Some(closed.game_id));
+        if let Some(index) = index {
+            self.covers.select(index);
+            if closed.play {
+                self.start_selected_game(rl, context);
+            }
         }
     }
+
+    /// Shows `message` on the bottom bar, after the messages already there.
+    fn show_status(&mut self, message: String, now: f64) {
+        match &self.status {
+            Some((_, shown_at)) if now - shown_at < STATUS_SECONDS => {
+                if self.queued_status.len() < MAX_QUEUED_STATUS {
+                    self.queued_status.push_back(message);
+                }
+            }
+            _ => self.status = Some((message, now)),
+        }
+    }
+
+    /// Only a cheap frame is drawn while a game runs, so window events keep
+    /// being processed.
+    fn draw_while_playing(&self, d: &mut RaylibDrawHandle) {
+        let title = format!("Playing {}...", self.launcher.running_title().unwrap_or(""));
+        let (s_wid, s_heig) = (d.get_screen_width(), d.get_screen_height());
+        d.clear_background(Color::BLACK);
+        d.draw_text(&title, s_wid / 2 - measure_text(&title, 20) / 2, s_heig / 2 - 10, 20, Color::WHITE);
+    }
 }
 
 impl AsScene for MainScreen {  
@@ -91,10 +324,30 @@ impl AsScene for MainScreen {
         self.name
     }
 
-    fn load(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {   
-        rl.set_target_fps(60);
+    fn receive(&mut self, payload: ScenePayload) {
+        match payload.take::<DetailsClosed>() {
+            Ok(closed) => self.closed_details = Some(closed),
+            Err(payload) => {
+                if payload.get::<OptionsClosed>().is_some_and(|closed| closed.library_changed) {
+                    self.reload_covers = true;
+                }
+            }
+        }
+    }
 
-        self.deltatime = (self.end_time - self.start_time) as f32;
+    fn load(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread, context: &mut AppContext) {   
+        self.load_covers(context);
+
+        // Menu actions added by plugins, laid out at the bottom.
+        let actions = context.get::<PluginHost>().map(PluginHost::menu_actions).unwrap_or_default();
+        self.action_buttons = actions.iter().enumerate().map(|(i, action)| {
+            Button::new(
+                Vector2::new(10.0 + i as f32 * 130.0, 440.0),
+                Vector2::new(120.0, 30.0),
+                &action.label,
+                ButtonStyle::build_default_style()
+            ).with_action(&format!("plugin:{}", i))
+        }).collect::<Vec<Button>>();
     
         let mut standard_style = ButtonStyle::from_default(
             Color::BLUE, 
@@ -110,70 +363,48 @@ impl AsScene for MainScreen {
             Color::DARKBLUE, 
             Color::GRAY
         );
+
+        standard_style.edit_focus_style(
+            Color::RAYWHITE,
+            Color::BLUE
+        );
+
+        standard_style.edit_scales(1.05, 0.95, 1.05);
+        standard_style.edit_transition(0.15, ease::cubic_out);
     
-        for button in &mut self.buttons{
+        for button in self.buttons.iter_mut().chain(self.action_buttons.iter_mut()) {
             button.style = standard_style;
         }
     }
 
-    fn update(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) -> SceneCommand {
-        self.start_time = rl.get_time() as f32;
+    fn update(&mut self, rl: &mut RaylibHandle, _thread: &RaylibThread, dt: f32, input: &InputState, context: &mut AppContext) -> SceneCommand {
+        if self.launcher.is_running() {
+            return self.update_while_playing(rl, context);
+        }
+        if let Some(closed) = self.closed_details.take() {
+            self.details_closed(closed, rl, context);
+        }
+        if self.reload_covers {
+            self.reload_covers = false;
+            self.load_covers(context);
+        }
+        let cover_fit = context.get::<Config>().map_or(self.cover_fit, |config| config.library.cover_fit);
+        if cover_fit != self.cover_fit {
+            self.cover_fit = cover_fit;
+            self.covers.set_fit(cover_fit);
+        }
+        let columns = context.get::<Config>().map_or(self.grid.columns(), |config| config.library.grid_columns);
+        if columns != self.grid.columns() {
+            self.grid.set_columns(columns);
+        }
+        self.draw_placeholders(context);
 
         
-        match self.state { // Opening Animation
-            0 => {
-                for rectangle in self.opening_rectangle.iter_mut() {
-                    if self.state == 0 {
-                        rectangle.width = rl.get_screen_width() as f32;
-                        rectangle.height = rl.get_screen_height() as f32 / 2.0;
-                    }
-                }
-
-                if self.frame_counter >= 180 {
-                    self.state = 1;
-                    self.frame_counter = 0;
-                }
-            }
-
-            1 => {
-                for rectangle in self.opening_rectangle.iter_mut() {
-                    if self.state == 0 {
-                        rectangle.width = rl.get_screen_width() as f32;
-                        rectangle.height = rl.get_screen_height() as f32 / 2.0;
-                    }
-                }
-
-                self.opening_rectangle[0].height = ease::expo_out(
-                    self.frame_counter as f32, 
-                    (rl.get_screen_height() / 2) as f32, -rl.get_screen_height() as f32 * 0.52, 360.0);
-                
-                self.opening_rectangle[1].y = ease::expo_out(
-                    self.frame_counter as f32, 
-                    (rl.get_screen_height() / 2) as f32, rl.get_screen_height() as f32 * 0.52, 360.0);
-
-                if self.frame_counter >= 360 {
-                    println!("bruh.");
-                    self.state = 2;
-                    self.frame_counter = 0;
-                }
-            }
-            _ => {}
-        }
         let screen_size: Vector2 = Vector2::new(
             rl.get_screen_width() as f32, 
             rl.get_screen_height() as f32
         );
 
-        let cursor = rl.get_mouse_position();
-
-        for mut button in &mut self.buttons {
-            if button.is_hover(cursor) {
-                button.is_active = true;
-            } else {
-                button.is_active = false;
-            };
-        }
-
         self.buttons[1].position.x = (screen_size.x / 2.0) - self.buttons[1].size.x / 2.0; // Options display
         
         self.buttons[2].position.x = (screen_size.x - 10.0) - self.buttons[2].size.x; // Exit display
@@ -187,58 +418,197 @@ impl AsScene for MainScreen {
         self.buttons[4].position.y = screen_size.y / 2.0;
 
         self.buttons[3].position.y = screen_size.y / 2.0; // Go Left display
+
+        for button in &mut self.action_buttons {
+            button.position.y = screen_size.y - 40.0; // Plugin actions display
+        }
         
-        // self.simple_cover.pos = Vector2::new(20.0, 80.0);
-        self.simple_cover.size = Vector2::new(screen_size.x - 40.0, screen_size.y - 80.0);
+        for cover in self.covers.covers.iter_mut() {
+            cover.size = Vector2::new(screen_size.x - 40.0, screen_size.y - 80.0);
+        }
         
-        for button in &mut self.buttons {
-            if button.is_hover(cursor) && rl.is_mouse_button_down(MouseButton::MOUSE_LEFT_BUTTON) {
-                button.is_down = true;
-            } else {
-                button.is_down = false;
+        // Mouse and directional navigation share the buttons: moving the mouse
+        // drops the focus, pressing a direction brings it back.
+        self.focus.set_areas(self.buttons.iter().chain(self.action_buttons.iter()).map(Button::bounds).collect());
+        if input.mouse_moved {
+            self.focus.clear();
+        }
+        // On the grid, directions move the selection until they leave it
+        // through the top, reaching the buttons; Down on them goes back.
+        if let Some(direction) = input.direction() {
+            match (self.view, self.focus.focused()) {
+                (LibraryView::Grid, None) => {
+                    if !self.grid.navigate(&mut self.covers, direction) && direction == Action::Up {
+                        self.focus.navigate(direction);
+                    }
+                }
+                (LibraryView::Grid, Some(index)) if index < 3 && direction == Action::Down => self.focus.clear(),
+                _ => {
+                    self.focus.navigate(direction);
+                }
             }
         }
+        let focused = self.focus.focused();
 
-        if self.buttons[2].is_hover(cursor) && rl.is_mouse_button_pressed(MouseButton::MOUSE_LEFT_BUTTON) {
-                return SceneCommand::exit_program();
+        let mut clicked = vec![];
+        for (index, button) in self.buttons.iter_mut().chain(self.action_buttons.iter_mut()).enumerate() {
+            let events = button.update(input, focused == Some(index));
+            button.animate(dt);
+            if events.contains(&ButtonEvent::Clicked) {
+                clicked.push(button.action.clone());
             }
+        }
+        let button_clicked = !clicked.is_empty();
+        for action in clicked {
+            if let Some(command) = self.activate(&action, rl, context) {
+                return command;
+            }
+        }
 
-        {
-            let mut d: RaylibDrawHandle = rl.begin_drawing(&thread);
-            d.clear_background(Color::RAYWHITE);
+        if input.pressed(Action::Menu) {
+            self.toggle_view();
+        }
 
-            self.simple_cover.draw(&mut d, screen_size);
-            self.simple_cover.draw_lines(&mut d);
+        let open_details = match self.view {
+            LibraryView::Single => {
+                if input.pressed(Action::PageLeft) || input.wheel > 0.0 {
+                    self.covers.previous();
+                }
+                if input.pressed(Action::PageRight) || input.wheel < 0.0 {
+                    self.covers.next();
+                }
 
-            d.draw_rectangle(
-                0,
-                0, 
-                screen_size.x as i32, 
-                80, 
-                Color::WHITE
-            );
-            d.draw_line_ex( 
-                // This line marks the upside of buttons and the downside of covers,
-                // it means that cover area is screen's size minus 80 pixels.
-                Vector2::new(0.0, 80.0),
-                Vector2::new(screen_size.x, 80.0), 
-                3.0, 
-                Color::BLACK
-            );
+                let cover_clicked = input.mouse_pressed && !button_clicked && self.covers.selected_cover()
+                    .is_some_and(|cover| cover.bounds().check_collision_point_rec(input.mouse_position));
+                cover_clicked || (focused.is_none() && input.pressed(Action::Confirm))
+            }
+            LibraryView::Grid => {
+                let area = self.grid_area(screen_size);
+                if input.pressed(Action::PageLeft) {
+                    self.grid.page(&mut self.covers, area, -1);
+                }
+                if input.pressed(Action::PageRight) {
+                    self.grid.page(&mut self.covers, area, 1);
+                }
+                self.grid.scroll_by(-input.wheel * 60.0);
 
-            for button in &mut self.buttons {
-                button.draw(&mut d, self.deltatime)
+                // Clicking a thumbnail selects it, clicking it again opens it.
+                let clicked_cover = self.grid.index_at(&self.covers, area, input.mouse_position)
+                    .filter(|_| input.mouse_pressed && !button_clicked);
+                let open_details = match clicked_cover {
+                    Some(index) if index == self.covers.selected() => true,
+                    Some(index) => {
+                        self.covers.select(index);
+                        false
+                    }
+                    None => focused.is_none() && input.pressed(Action::Confirm),
+                };
+                self.grid.update(&self.covers, area, dt);
+                open_details
             }
-            if self.state < 2 {
-                d.draw_rectangle_rec(self.opening_rectangle[1], Color::BLACK);
-                d.draw_rectangle_rec(self.opening_rectangle[0], Color::BLACK);
+        };
+        self.covers.update(dt);
+        if open_details {
+            if let Some(command) = self.open_details() {
+                return command;
             }
         }
-        self.end_time = rl.get_time() as f32;
-        self.deltatime = self.end_time - self.start_time;
-        self.frame_counter += 1;
+
+        let now = rl.get_time();
+        let plugin_errors = context.get_mut::<PluginHost>().map(PluginHost::take_errors).unwrap_or_default();
+        for error in plugin_errors {
+            self.show_status(error.to_string(), now);
+        }
+        let expired = self.status.as_ref().is_none_or(|(_, shown_at)| now - shown_at >= STATUS_SECONDS);
+        if expired {
+            if let Some(message) = self.queued_status.pop_front() {
+                self.status = Some((message, now));
+            }
+        }
+
         SceneCommand::continue_program()
     }
+
+    fn draw(&mut self, d: &mut RaylibDrawHandle, context: &AppContext) {
+        if self.launcher.is_running() {
+            return self.draw_while_playing(d);
+        }
+
+        let screen_size: Vector2 = Vector2::new(
+            d.get_screen_width() as f32, 
+            d.get_screen_height() as f32
+        );
+        let now = d.get_time();
+        let theme = context.get::<Config>().map(|config| config.theme).unwrap_or_default();
+        d.clear_background(theme.background());
+
+        match (self.covers.selected_cover(), context.get::<AssetCache>()) {
+            (Some(_), Some(assets)) => match self.view {
+                LibraryView::Single => self.covers.draw(d, assets, screen_size),
+                LibraryView::Grid => self.grid.draw(d, &self.covers, assets, self.grid_area(screen_size)),
+            },
+            _ => {
+                let message = "There's no game there!";
+                d.draw_text(
+                    message,
+                    screen_size.x as i32 / 2 - measure_text(message, 20) / 2,
+                    screen_size.y as i32 / 2 + 30,
+                    20,
+                    Color::GRAY
+                );
+            }
+        }
+
+        d.draw_rectangle(
+            0,
+            0, 
+            screen_size.x as i32, 
+            80, 
+            theme.panel()
+        );
+        d.draw_line_ex( 
+            // This line marks the upside of buttons and the downside of covers,
+            // it means that cover area is screen's size minus 80 pixels.
+            Vector2::new(0.0, 80.0),
+            Vector2::new(screen_size.x, 80.0), 
+            3.0, 
+            theme.text()
+        );
+
+        for button in self.buttons.iter().chain(self.action_buttons.iter()) {
+            button.draw(d)
+        }
+
+        if let Some((message, shown_at)) = &self.status {
+            if now - shown_at < STATUS_SECONDS {
+                d.draw_rectangle(0, screen_size.y as i32 - 30, screen_size.x as i32, 30, Color::BLACK.fade(0.7));
+                d.draw_text(message, 10, screen_size.y as i32 - 25, 20, Color::WHITE);
+            }
+        }
+    }
     
-    fn unload(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread){}
-}
\ No newline at end of file
+    /// Covers are dropped until the menu is back, letting the cache evict
+    /// their textures. A game still running is killed, so it doesn't outlive Starframe, and
+    /// its session is recorded.
+    fn unload(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread, context: &mut AppContext) {
+        if let Some(report) = self.launcher.kill() {
+            println!("{}", report);
+            record_session(&report, context);
+        }
+        match context.get_mut::<AssetCache>() {
+            Some(assets) => self.covers.clear(assets),
+            None => self.covers = CoverBook::new(),
+        }
+        self.action_buttons.clear();
+    }
+}
+
+/// Adds the session of a game that ended to its play time.
+fn record_session(report: &ExitReport, context: &mut AppContext) {
+    if let Some(library) = context.get_mut::<GameLibrary>() {
+        let seconds = report.play_time.as_secs();
+        if let Err(err) = library.update(report.game_id, |game| game.record_session(seconds)) {
+            println!("can't record play time: {}", err);
+        }
+    }
+}
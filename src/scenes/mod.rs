@@ -1,5 +1,9 @@
+pub mod details;
 pub mod menu;
 pub mod opening;
+pub mod options;
 
+pub use details::GameDetails;
 pub use menu::MainScreen;
-pub use opening::Opening;
\ No newline at end of file
+pub use opening::Opening;
+pub use options::Options;
\ No newline at end of file
@@ -1,4 +1,4 @@
-use crate::elements::{AsScene, SceneCommand};
+use crate::elements::{Action, AppContext, AsScene, InputState, SceneCommand, Transition};
 use raylib::prelude::*;
 use raylib::ease;
 
@@ -6,7 +6,8 @@ pub struct Opening {
     name: &'static str,
     // Insert future use variables there.
     title: &'static str,
-    frame_counter: i32,
+    /// Seconds spent on the current state.
+    elapsed: f32,
     state: i32,
     alpha: f32,
 }
@@ -17,62 +18,62 @@ impl Opening {
             name: "Opening",
 
             title: "Starframe",
-            frame_counter: 0,
+            elapsed: 0.0,
             state: 0,
             alpha: 0.0,
         }
     }
 }
 
+impl Opening {
+    /// Gives its place to the menu, closing curtains over the title.
+    fn leave() -> SceneCommand {
+        SceneCommand::replace_scene("Menu").with_transition(Transition::Curtain, 1.6)
+    }
+}
+
 impl AsScene for Opening {
     fn name(&self) -> &'static str {
         self.name
     }
 
-    fn load(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread){
-        rl.set_target_fps(60);
+    /// Starts the splash over, so coming back to it plays it again.
+    fn load(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread, _context: &mut AppContext) {
+        self.elapsed = 0.0;
+        self.state = 0;
+        self.alpha = 0.0;
     }
-    fn update(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) -> SceneCommand {
-        let (s_wid, s_heig) = (rl.get_screen_width(), rl.get_screen_height());
+    fn update(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread, dt: f32, input: &InputState, _context: &mut AppContext) -> SceneCommand {
+        if input.pressed(Action::Confirm) || input.pressed(Action::Back) {
+            return Opening::leave() // Skips the opening.
+        }
+
+        self.elapsed += dt;
         match self.state {
             0 => {
-                self.alpha = ease::sine_in(self.frame_counter as f32, 0.0, 2.0, 210.0);
-                if self.frame_counter >= 210 {
+                self.alpha = ease::sine_in(self.elapsed, 0.0, 2.0, 3.5);
+                if self.elapsed >= 3.5 {
                     self.state = 1;
-                    self.frame_counter = 0;
+                    self.elapsed = 0.0;
                 }
             }
 
-            1 => {
-                if self.frame_counter >= 840 {
-                    self.alpha = 1.0;
-                    self.state = 2;
-                    self.frame_counter = 0;
-                }
-            }
-
-            2 => {
-                self.alpha = ease::sine_out(self.frame_counter as f32, 1.0, -2.0, 210.0);
-                if self.frame_counter >= 260 {
-                    return SceneCommand::jump_to_scene("Menu")
-                }
-            }
+            1 if self.elapsed >= 14.0 => return Opening::leave(),
             _ => {}
         }
-        {
-            let mut d: RaylibDrawHandle = rl.begin_drawing(&thread);
-            d.clear_background(Color::BLACK);
-            d.draw_text(
-                self.title, 
-                s_wid / 2 - measure_text(self.title, 20) / 2,
-                s_heig / 2 - 10,
-                20, 
-                Color::WHITE.fade(self.alpha)
-            );
-        }
-
-        self.frame_counter += 1;
         SceneCommand::continue_program()
     }
-    fn unload(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread){}
+
+    fn draw(&mut self, d: &mut RaylibDrawHandle, _context: &AppContext) {
+        let (s_wid, s_heig) = (d.get_screen_width(), d.get_screen_height());
+        d.clear_background(Color::BLACK);
+        d.draw_text(
+            self.title, 
+            s_wid / 2 - measure_text(self.title, 20) / 2,
+            s_heig / 2 - 10,
+            20, 
+            Color::WHITE.fade(self.alpha)
+        );
+    }
+    fn unload(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread, _context: &mut AppContext) {}
 }
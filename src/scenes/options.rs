@@ -0,0 +1,827 @@
+use crate::elements::config::MAX_GRID_COLUMNS;
+use crate::elements::{scan_library, Action, AppContext, AsScene, Binding, Config, CoverFit, EmulatorError, EmulatorProfile, EmulatorRegistry, GameLibrary, InputConfig, InputMap, InputState, LibraryFolder, PluginHost, SceneCommand, Theme, Transition};
+use raylib::prelude::*;
+use std::path::PathBuf;
+
+/// Name the options scene is registered with.
+pub const OPTIONS: &str = "Options";
+
+/// Payload handed back to the scene below when `Options` is closed.
+pub struct OptionsClosed {
+    /// Folders were changed or rescanned, so views of the library are outdated.
+    pub library_changed: bool,
+}
+
+/// Window sizes offered on the Display section.
+const RESOLUTIONS: [(i32, i32); 5] = [(640, 480), (800, 600), (1024, 768), (1280, 720), (1920, 1080)];
+/// FPS caps offered on the Display section. `0` is uncapped.
+const FPS_CAPS: [u32; 5] = [30, 60, 120, 144, 0];
+
+const HEADER_HEIGHT: i32 = 50;
+const TABS_WIDTH: i32 = 150;
+const ROW_HEIGHT: i32 = 32;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Display,
+    Input,
+    Library,
+    Emulators,
+    Theme,
+    Plugins,
+}
+
+impl Section {
+    const ALL: [Section; 6] = [
+        Section::Display,
+        Section::Input,
+        Section::Library,
+        Section::Emulators,
+        Section::Theme,
+        Section::Plugins,
+    ];
+
+    fn title(&self) -> &'static str {
+        match self {
+            Section::Display => "Display",
+            Section::Input => "Input",
+            Section::Library => "Library",
+            Section::Emulators => "Emulators",
+            Section::Theme => "Theme",
+            Section::Plugins => "Plugins",
+        }
+    }
+}
+
+/// A setting shown on a section, as its name and current value.
+struct OptionRow {
+    label: String,
+    value: String,
+}
+
+impl OptionRow {
+    fn new(label: &str, value: String) -> OptionRow {
+        OptionRow {
+            label: String::from(label),
+            value,
+        }
+    }
+}
+
+/// Fields of an emulator profile edited on the Emulators section, indexed
+/// like the values of `Editing::Emulator`.
+const PROFILE_FIELDS: [&str; 4] = ["Executable", "Arguments", "Platforms", "Extensions"];
+
+/// A setting being typed or waiting for a key, shown over the rows. While
+/// it's open, input actions are ignored, since they're bound to keys used for
+/// typing.
+enum Editing {
+    /// Waiting for the key to bind to the action.
+    Binding(Action),
+    /// Path of a library folder to add.
+    NewFolder(String),
+    /// Name of an emulator profile to add.
+    NewEmulator(String),
+    /// Fields of the profile called `name`, comma separated for lists.
+    Emulator { name: String, field: usize, values: [String; 4] },
+}
+
+impl Editing {
+    fn emulator(profile: &EmulatorProfile) -> Editing {
+        Editing::Emulator {
+            name: profile.name.clone(),
+            field: 0,
+            values: [
+                profile.executable.to_string_lossy().into_owned(),
+                profile.arguments.clone(),
+                profile.platforms.join(", "),
+                profile.extensions.join(", "),
+            ],
+        }
+    }
+
+    /// Text the typed characters go to, if any.
+    fn text_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Editing::Binding(_) => None,
+            Editing::NewFolder(text) | Editing::NewEmulator(text) => Some(text),
+            Editing::Emulator { field, values, .. } => Some(&mut values[*field]),
+        }
+    }
+}
+
+/// Splits a comma separated list, dropping blank items.
+fn split_list(text: &str) -> Vec<String> {
+    text.split(',').map(str::trim).filter(|item| !item.is_empty()).map(String::from).collect()
+}
+
+/// Name of a key bound to `action` on `map` for hints, preferring keyboard
+/// keys.
+fn key_name(map: &InputMap, action: Action) -> String {
+    let key = map.bindings_of(action).find(|binding| matches!(binding, Binding::Key(_)));
+    key.or_else(|| map.bindings_of(action).next()).map(|binding| binding.to_string()).unwrap_or_else(|| String::from("?"))
+}
+
+/// Settings screen, split on sections switched with PageLeft and PageRight.
+/// Up and Down pick a setting, Left and Right change it, and Confirm changes it
+/// or opens it for typing: key bindings, new library folders and emulator
+/// profiles. Menu removes the selected folder or profile, or restores the
+/// default keys of an action.
+///
+/// Changes go to the `Config` on `AppContext` and apply right away when they
+/// can; the config is saved when leaving with Back. Emulator profiles are saved
+/// as soon as they change, without the profiles added by plugins.
+pub struct Options {
+    section: usize,
+    row: usize,
+    /// First row shown, when a section has more rows than fit on screen.
+    scroll: usize,
+    rows: Vec<OptionRow>,
+    library_changed: bool,
+    /// Result of the last change, like a scan report.
+    message: Option<String>,
+    editing: Option<Editing>,
+    /// Section and row where Menu was pressed once to remove something,
+    /// waiting for a second press.
+    removing: Option<(usize, usize)>,
+}
+
+impl Options {
+    pub fn new() -> Options {
+        Options {
+            section: 0,
+            row: 0,
+            scroll: 0,
+            rows: vec![],
+            library_changed: false,
+            message: None,
+            editing: None,
+            removing: None,
+        }
+    }
+
+    fn current_section(&self) -> Section {
+        Section::ALL[self.section]
+    }
+
+    /// Rows of the current section, read from the services on `context`.
+    fn build_rows(&self, context: &AppContext) -> Vec<OptionRow> {
+        let on_off = |on: bool| String::from(if on { "On" } else { "Off" });
+        match self.current_section() {
+            Section::Display => {
+                let display = match context.get::<Config>() {
+                    Some(config) => config.window.clone(),
+                    None => return vec![],
+                };
+                let fps = match display.fps {
+                    0 => String::from("Uncapped"),
+                    fps => fps.to_string(),
+                };
+                vec![
+                    OptionRow::new("Fullscreen", on_off(display.fullscreen)),
+                    OptionRow::new("Resolution", format!("{}x{}", display.width, display.height)),
+                    OptionRow::new("FPS cap", fps),
+                    OptionRow::new("VSync", on_off(display.vsync)),
+                ]
+            }
+            Section::Input => {
+                let default_map = InputMap::default_bindings();
+                let map = context.get::<InputMap>().unwrap_or(&default_map);
+                let mut rows: Vec<OptionRow> = Action::ALL.iter().map(|action| {
+                    let bindings: Vec<String> = map.bindings_of(*action).map(|b| b.to_string()).collect();
+                    OptionRow::new(&format!("{:?}", action), bindings.join(", "))
+                }).collect();
+                rows.push(OptionRow::new("Reset input settings", String::new()));
+                rows
+            }
+            Section::Library => {
+                let mut rows: Vec<OptionRow> = match context.get::<GameLibrary>() {
+                    Some(library) => library.folders().iter().map(|folder| {
+                        let depth = if folder.recursive { "Subfolders" } else { "Top only" };
+                        let value = match &folder.platform {
+                            Some(platform) => format!("{}, {}", depth, platform),
+                            None => String::from(depth),
+                        };
+                        OptionRow::new(&folder.path.to_string_lossy(), value)
+                    }).collect(),
+                    None => vec![],
+                };
+                rows.push(OptionRow::new("Add folder", String::new()));
+                rows.push(OptionRow::new("Rescan folders", String::new()));
+                rows
+            }
+            Section::Emulators => {
+                let mut rows: Vec<OptionRow> = match context.get::<EmulatorRegistry>() {
+                    Some(emulators) => emulators.profiles().iter().map(|profile| {
+                        let status = match profile.validate().and_then(|_| profile.find_executable()) {
+                            Ok(_) => String::from("Ready"),
+                            Err(err) => err.to_string(),
+                        };
+                        OptionRow::new(&profile.name, status)
+                    }).collect(),
+                    None => return vec![],
+                };
+                rows.push(OptionRow::new("Add emulator", String::new()));
+                rows
+            }
+            Section::Theme => match context.get::<Config>() {
+                Some(config) => vec![
+                    OptionRow::new("Theme", format!("{:?}", config.theme)),
+                    OptionRow::new("Cover fit", format!("{:?}", config.library.cover_fit)),
+                    OptionRow::new("Grid columns", config.library.grid_columns.to_string()),
+                ],
+                None => vec![],
+            },
+            Section::Plugins => {
+                let (plugins, disabled) = match (context.get::<PluginHost>(), context.get::<Config>()) {
+                    (Some(plugins), Some(config)) => (plugins, &config.plugins.disabled),
+                    _ => return vec![],
+                };
+                // Plugins enabled since startup, or that failed, aren't loaded.
+                let loaded = plugins.plugin_names();
+                plugins.available_plugins().iter().map(|name| {
+                    let status = if disabled.contains(name) {
+                        "Disabled"
+                    } else if loaded.contains(&name.as_str()) {
+                        "Enabled"
+                    } else {
+                        "Not loaded"
+                    };
+                    OptionRow::new(name, String::from(status))
+                }).collect()
+            }
+        }
+    }
+
+    /// Changes the selected setting, going forward on its values when `delta`
+    /// is positive and backwards otherwise. Rows that can't be changed ignore it.
+    fn change(&mut self, delta: i32, rl: &mut RaylibHandle, context: &mut AppContext) {
+        match self.current_section() {
+            Section::Display => self.change_display(delta, rl, context),
+            Section::Library => self.change_library(context),
+            Section::Theme => {
+                let config = match context.get_mut::<Config>() {
+                    Some(config) => config,
+                    None => return,
+                };
+                match self.row {
+                    0 => {
+                        config.theme = match config.theme {
+                            Theme::Light => Theme::Dark,
+                            Theme::Dark => Theme::Light,
+                        };
+                    }
+                    1 => {
+                        let fit = &mut config.library.cover_fit;
+                        let current = CoverFit::ALL.iter().position(|f| f == fit);
+                        *fit = CoverFit::ALL[cycle(current, delta, CoverFit::ALL.len())];
+                    }
+                    _ => {
+                        let columns = &mut config.library.grid_columns;
+                        *columns = cycle(Some(*columns - 1), delta, MAX_GRID_COLUMNS) + 1;
+                    }
+                }
+            }
+            Section::Plugins => {
+                let name = match self.rows.get(self.row) {
+                    Some(row) => row.label.clone(),
+                    None => return,
+                };
+                if let Some(config) = context.get_mut::<Config>() {
+                    let disabled = &mut config.plugins.disabled;
+                    match disabled.iter().position(|d| *d == name) {
+                        Some(index) => {
+                            disabled.remove(index);
+                        }
+                        None => disabled.push(name),
+                    }
+                    self.message = Some(String::from("Plugin changes apply on restart"));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn change_display(&mut self, delta: i32, rl: &mut RaylibHandle, context: &mut AppContext) {
+        let display = match context.get_mut::<Config>() {
+            Some(config) => &mut config.window,
+            None => return,
+        };
+        match self.row {
+            0 => {
+                display.fullscreen = !display.fullscreen;
+                if rl.is_window_fullscreen() != display.fullscreen {
+                    rl.toggle_fullscreen();
+                }
+            }
+            1 => {
+                let current = RESOLUTIONS.iter().position(|r| *r == (display.width, display.height));
+                let (width, height) = RESOLUTIONS[cycle(current, delta, RESOLUTIONS.len())];
+                display.width = width;
+                display.height = height;
+                if !display.fullscreen {
+                    rl.set_window_size(width, height);
+                }
+            }
+            2 => {
+                let current = FPS_CAPS.iter().position(|fps| *fps == display.fps);
+                display.fps = FPS_CAPS[cycle(current, delta, FPS_CAPS.len())];
+                rl.set_target_fps(display.fps);
+            }
+            3 => {
+                display.vsync = !display.vsync;
+                self.message = Some(String::from("VSync applies on restart"));
+            }
+            _ => {}
+        }
+    }
+
+    /// Toggles whether the selected folder is scanned with its subfolders, or
+    /// rescans every folder from the last row.
+    fn change_library(&mut self, context: &mut AppContext) {
+        let mut library = match context.remove::<GameLibrary>() {
+            Some(library) => library,
+            None => return,
+        };
+
+        let folder_count = library.folders().len();
+        match library.folders().get(self.row).map(|folder| folder.path.clone()) {
+            Some(path) => {
+                library.update_folder(&path, |folder| folder.recursive = !folder.recursive);
+                self.message = Some(String::from("Rescan to apply folder changes"));
+            }
+            // "Add folder" is opened by `activate`.
+            None if self.row == folder_count => {}
+            None => {
+                let no_emulators = EmulatorRegistry::new(EmulatorRegistry::default_path());
+                let report = scan_library(&mut library, context.get::<EmulatorRegistry>().unwrap_or(&no_emulators));
+                if report.has_changes() {
+                    self.library_changed = true;
+                }
+                self.message = Some(format!("Library scan: {}", report));
+            }
+        }
+        context.insert(library);
+    }
+
+    /// Runs Confirm on the selected row: opens it for typing or for a key
+    /// when it takes one, and changes it forward otherwise.
+    fn activate(&mut self, rl: &mut RaylibHandle, context: &mut AppContext) {
+        match self.current_section() {
+            Section::Input if self.row < Action::ALL.len() => {
+                self.editing = Some(Editing::Binding(Action::ALL[self.row]));
+            }
+            Section::Input => {
+                let map = match context.get_mut::<Config>() {
+                    Some(config) => {
+                        config.input = InputConfig::default();
+                        config.input.input_map()
+                    }
+                    None => InputMap::default_bindings(),
+                };
+                context.insert(map);
+                self.message = Some(String::from("Input settings reset"));
+            }
+            Section::Library if self.row + 2 == self.rows.len() => {
+                self.editing = Some(Editing::NewFolder(String::new()));
+            }
+            Section::Emulators => {
+                let profile = context.get::<EmulatorRegistry>().and_then(|emulators| emulators.profiles().get(self.row));
+                self.editing = Some(match profile {
+                    Some(profile) => Editing::emulator(profile),
+                    None => Editing::NewEmulator(String::new()),
+                });
+            }
+            _ => self.change(1, rl, context),
+        }
+    }
+
+    /// Runs Menu on the selected row: removes the selected folder or profile
+    /// on a second press, or restores the default keys of an action.
+    fn remove(&mut self, context: &mut AppContext) {
+        let label = match self.rows.get(self.row) {
+            Some(row) => row.label.clone(),
+            None => return,
+        };
+        let removable = match self.current_section() {
+            Section::Input => {
+                let action = match Action::ALL.get(self.row) {
+                    Some(action) => *action,
+                    None => return,
+                };
+                if let Some(config) = context.get_mut::<Config>() {
+                    config.input.keys.remove(action.name());
+                    let map = config.input.input_map();
+                    context.insert(map);
+                    self.message = Some(format!("{} uses its default keys", label));
+                }
+                return;
+            }
+            Section::Library => self.row + 2 < self.rows.len(),
+            Section::Emulators => self.row + 1 < self.rows.len(),
+            _ => false,
+        };
+        if !removable {
+            return;
+        }
+        if self.removing != Some((self.section, self.row)) {
+            self.removing = Some((self.section, self.row));
+            let key = context.get::<InputMap>().map(|map| key_name(map, Action::Menu)).unwrap_or_default();
+            self.message = Some(format!("Press {} again to remove {}", key, label));
+            return;
+        }
+
+        self.removing = None;
+        if self.current_section() == Section::Library {
+            if let Some(library) = context.get_mut::<GameLibrary>() {
+                if let Some(path) = library.folders().get(self.row).map(|folder| folder.path.clone()) {
+                    library.remove_folder(&path);
+                    self.message = Some(String::from("Folder removed, rescan to drop its games"));
+                }
+            }
+        } else {
+            self.message = Some(match edit_emulators(context, |emulators| {
+                emulators.remove(&label);
+                Ok(())
+            }) {
+                Ok(()) => format!("{} removed", label),
+                Err(err) => err.to_string(),
+            });
+        }
+    }
+
+    /// Feeds the frame's input to the setting being edited. Enter confirms,
+    /// Escape cancels and Tab or Up/Down switch the field of profiles.
+    fn update_editing(&mut self, input: &InputState, context: &mut AppContext) {
+        let mut editing = match self.editing.take() {
+            Some(editing) => editing,
+            None => return,
+        };
+
+        if let Editing::Binding(action) = editing {
+            match input.keys.first() {
+                Some(KeyboardKey::KEY_ESCAPE) => {}
+                Some(key) => self.bind_key(action, *key, context),
+                None => self.editing = Some(editing),
+            }
+            return;
+        }
+
+        if let Some(text) = editing.text_mut() {
+            text.push_str(&input.typed);
+        }
+        let mut done = None;
+        for key in &input.keys {
+            match key {
+                KeyboardKey::KEY_BACKSPACE => {
+                    editing.text_mut().map(String::pop);
+                }
+                KeyboardKey::KEY_TAB | KeyboardKey::KEY_DOWN | KeyboardKey::KEY_UP => {
+                    if let Editing::Emulator { field, .. } = &mut editing {
+                        let step = if *key == KeyboardKey::KEY_UP { PROFILE_FIELDS.len() - 1 } else { 1 };
+                        *field = (*field + step) % PROFILE_FIELDS.len();
+                    }
+                }
+                KeyboardKey::KEY_ENTER => done = Some(true),
+                KeyboardKey::KEY_ESCAPE => done = Some(false),
+                _ => {}
+            }
+        }
+
+        match done {
+            Some(true) => self.finish_editing(editing, context),
+            Some(false) => {}
+            None => self.editing = Some(editing),
+        }
+    }
+
+    /// Makes `key` the only keyboard key of `action`, keeping its gamepad and
+    /// mouse bindings.
+    fn bind_key(&mut self, action: Action, key: KeyboardKey, context: &mut AppContext) {
+        if let Some(config) = context.get_mut::<Config>() {
+            config.input.keys.insert(action.name().to_string(), vec![key as i32]);
+            let map = config.input.input_map();
+            context.insert(map);
+            self.message = Some(format!("{:?} bound to {}", action, Binding::Key(key)));
+        }
+    }
+
+    /// Applies a confirmed edit. Profiles that don't validate stay open, so
+    /// the typed values aren't lost.
+    fn finish_editing(&mut self, editing: Editing, context: &mut AppContext) {
+        match editing {
+            Editing::Binding(_) => {}
+            Editing::NewFolder(path) => {
+                let path = PathBuf::from(path.trim());
+                if !path.is_dir() {
+                    self.message = Some(format!("{} is not a folder", path.display()));
+                } else if let Some(library) = context.get_mut::<GameLibrary>() {
+                    self.message = Some(String::from(if library.add_folder(LibraryFolder::new(path)) {
+                        "Folder added, rescan to find its games"
+                    } else {
+                        "Folder already added"
+                    }));
+                }
+            }
+            Editing::NewEmulator(name) => {
+                let name = name.trim();
+                if name.is_empty() {
+                    return;
+                }
+                let exists = context.get::<EmulatorRegistry>().is_some_and(|emulators| emulators.get(name).is_some());
+                if exists {
+                    self.message = Some(format!("{} already exists", name));
+                    return;
+                }
+                // Filled on the profile editor before it's registered.
+                let profile = EmulatorProfile::new(name, PathBuf::from(name.to_lowercase()), "{rom}");
+                self.editing = Some(Editing::emulator(&profile));
+            }
+            Editing::Emulator { name, field, values } => {
+                let [executable, arguments, platforms, extensions] = &values;
+                let mut profile = context.get::<EmulatorRegistry>()
+                    .and_then(|emulators| emulators.get(&name).cloned())
+                    .unwrap_or_else(|| EmulatorProfile::new(&name, PathBuf::new(), ""));
+                profile.executable = PathBuf::from(executable.trim());
+                profile.arguments = arguments.trim().to_string();
+                profile.platforms = split_list(platforms);
+                profile.extensions = split_list(extensions).iter().map(|e| e.trim_start_matches('.').to_string()).collect();
+
+                let saved = profile.validate().and_then(|_| edit_emulators(context, |emulators| {
+                    emulators.remove(&profile.name);
+                    emulators.add(profile.clone())
+                }));
+                match saved {
+                    Ok(()) => self.message = Some(format!("{} saved", name)),
+                    Err(err) => {
+                        self.message = Some(err.to_string());
+                        self.editing = Some(Editing::Emulator { name, field, values });
+                    }
+                }
+            }
+        }
+    }
+
+    fn switch_section(&mut self, section: usize) {
+        self.section = section % Section::ALL.len();
+        self.row = 0;
+        self.scroll = 0;
+    }
+
+    /// Where row `index` is drawn, if it's visible.
+    fn row_bounds(&self, index: usize, screen_size: Vector2) -> Option<Rectangle> {
+        let visible = index.checked_sub(self.scroll)?;
+        let y = HEADER_HEIGHT + 10 + visible as i32 * ROW_HEIGHT;
+        if y + ROW_HEIGHT > screen_size.y as i32 - 30 {
+            return None;
+        }
+        Some(Rectangle::new(
+            (TABS_WIDTH + 10) as f32,
+            y as f32,
+            screen_size.x - TABS_WIDTH as f32 - 20.0,
+            (ROW_HEIGHT - 4) as f32
+        ))
+    }
+
+    fn tab_bounds(index: usize) -> Rectangle {
+        Rectangle::new(
+            10.0,
+            (HEADER_HEIGHT + 10 + index as i32 * ROW_HEIGHT) as f32,
+            (TABS_WIDTH - 20) as f32,
+            (ROW_HEIGHT - 4) as f32
+        )
+    }
+
+    /// Draws the setting being edited on a panel over the rows.
+    fn draw_editing(&self, d: &mut RaylibDrawHandle, editing: &Editing, theme: Theme, screen_size: Vector2) {
+        let fields: Vec<(&str, &str)> = match editing {
+            Editing::Binding(_) => vec![],
+            Editing::NewFolder(path) => vec![("Folder path", path)],
+            Editing::NewEmulator(name) => vec![("Emulator name", name)],
+            Editing::Emulator { values, .. } => PROFILE_FIELDS.iter().zip(values.iter()).map(|(l, v)| (*l, v.as_str())).collect(),
+        };
+        let focused = match editing {
+            Editing::Emulator { field, .. } => *field,
+            _ => 0,
+        };
+        let title = match editing {
+            Editing::Binding(action) => format!("Press a key for {:?}, or Escape to cancel", action),
+            Editing::Emulator { name, .. } => format!("{}: Tab switches field, Enter saves", name),
+            _ => String::from("Enter confirms, Escape cancels"),
+        };
+
+        let height = 50 + fields.len() as i32 * (ROW_HEIGHT + 16);
+        let panel = Rectangle::new(
+            (TABS_WIDTH + 10) as f32,
+            (HEADER_HEIGHT + 10) as f32,
+            screen_size.x - TABS_WIDTH as f32 - 20.0,
+            height as f32
+        );
+        d.draw_rectangle_rec(panel, theme.panel());
+        d.draw_rectangle_lines_ex(panel, 2, Color::BLUE);
+        let (x, mut y) = (panel.x as i32 + 10, panel.y as i32 + 12);
+        d.draw_text(&title, x, y, 20, theme.text());
+        y += 38;
+        for (index, (label, value)) in fields.iter().enumerate() {
+            d.draw_text(label, x, y, 10, theme.faded_text());
+            let text = if index == focused { format!("{}_", value) } else { value.to_string() };
+            d.draw_text(&text, x, y + 14, 20, Color::DARKBLUE);
+            y += ROW_HEIGHT + 16;
+        }
+    }
+
+    /// Leaves, saving the config.
+    fn close(&self, context: &AppContext) -> SceneCommand {
+        if let Some(config) = context.get::<Config>() {
+            if let Err(err) = config.save() {
+                println!("{}", err);
+            }
+        }
+        SceneCommand::pop_scene()
+            .with_payload(OptionsClosed {
+                library_changed: self.library_changed,
+            })
+            .with_transition(Transition::Crossfade, 0.3)
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options::new()
+    }
+}
+
+/// Index `delta` steps away from `current` on a list of `len` values, wrapping
+/// around. Unknown values start over from the first.
+fn cycle(current: Option<usize>, delta: i32, len: usize) -> usize {
+    match current {
+        Some(current) => (current as i32 + delta).rem_euclid(len as i32) as usize,
+        None => 0,
+    }
+}
+
+/// Applies `edit` to the emulator registry on `context`, then to the one on
+/// disk, which is saved. The one on disk is opened again so profiles added by
+/// plugins aren't saved with it.
+fn edit_emulators<F>(context: &mut AppContext, edit: F) -> Result<(), EmulatorError>
+where
+    F: Fn(&mut EmulatorRegistry) -> Result<(), EmulatorError>,
+{
+    let emulators = match context.get_mut::<EmulatorRegistry>() {
+        Some(emulators) => emulators,
+        None => return Ok(()),
+    };
+    edit(emulators)?;
+    let mut on_disk = EmulatorRegistry::open(emulators.path().to_path_buf())?;
+    edit(&mut on_disk)?;
+    on_disk.save()
+}
+
+impl AsScene for Options {
+    fn name(&self) -> &'static str {
+        OPTIONS
+    }
+
+    fn load(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread, context: &mut AppContext) {
+        self.switch_section(0);
+        self.library_changed = false;
+        self.message = None;
+        self.editing = None;
+        self.removing = None;
+        self.rows = self.build_rows(context);
+    }
+
+    fn update(&mut self, rl: &mut RaylibHandle, _thread: &RaylibThread, _dt: f32, input: &InputState, context: &mut AppContext) -> SceneCommand {
+        if self.editing.is_some() {
+            self.update_editing(input, context);
+            self.rows = self.build_rows(context);
+            return SceneCommand::continue_program();
+        }
+        if input.pressed(Action::Back) {
+            return self.close(context);
+        }
+
+        let screen_size = Vector2::new(rl.get_screen_width() as f32, rl.get_screen_height() as f32);
+        if input.pressed(Action::PageLeft) {
+            self.switch_section(self.section + Section::ALL.len() - 1);
+        }
+        if input.pressed(Action::PageRight) {
+            self.switch_section(self.section + 1);
+        }
+        self.rows = self.build_rows(context);
+
+        let last_row = self.rows.len().saturating_sub(1);
+        if input.pressed(Action::Up) {
+            self.row = self.row.saturating_sub(1);
+        }
+        if input.pressed(Action::Down) {
+            self.row = (self.row + 1).min(last_row);
+        }
+        self.row = self.row.min(last_row);
+
+        let mut delta = 0;
+        if input.pressed(Action::Left) {
+            delta = -1;
+        }
+        if input.pressed(Action::Right) {
+            delta = 1;
+        }
+        let mut activated = input.pressed(Action::Confirm);
+
+        // Clicking a tab opens it, clicking a row changes it.
+        if input.mouse_pressed {
+            let point = input.mouse_position;
+            if let Some(tab) = (0..Section::ALL.len()).find(|i| Options::tab_bounds(*i).check_collision_point_rec(point)) {
+                self.switch_section(tab);
+                self.rows = self.build_rows(context);
+            }
+            let row = (0..self.rows.len())
+                .find(|i| self.row_bounds(*i, screen_size).is_some_and(|b| b.check_collision_point_rec(point)));
+            if let Some(row) = row {
+                self.row = row;
+                activated = true;
+            }
+        }
+
+        if self.removing.is_some_and(|removing| removing != (self.section, self.row)) {
+            self.removing = None;
+        }
+        if !self.rows.is_empty() {
+            if activated {
+                self.activate(rl, context);
+            } else if delta != 0 {
+                self.change(delta, rl, context);
+            } else if input.pressed(Action::Menu) {
+                self.remove(context);
+            }
+            self.rows = self.build_rows(context);
+        }
+
+        // Keeps the selected row on screen.
+        if self.row < self.scroll {
+            self.scroll = self.row;
+        }
+        while self.row > self.scroll && self.row_bounds(self.row, screen_size).is_none() {
+            self.scroll += 1;
+        }
+
+        SceneCommand::continue_program()
+    }
+
+    fn draw(&mut self, d: &mut RaylibDrawHandle, context: &AppContext) {
+        let screen_size = Vector2::new(d.get_screen_width() as f32, d.get_screen_height() as f32);
+        let theme = context.get::<Config>().map(|config| config.theme).unwrap_or_default();
+        d.clear_background(theme.background());
+
+        d.draw_rectangle(0, 0, screen_size.x as i32, HEADER_HEIGHT, theme.panel());
+        d.draw_text("Options", 15, 12, 30, theme.text());
+        let default_map = InputMap::default_bindings();
+        let map = context.get::<InputMap>().unwrap_or(&default_map);
+        let hint = format!(
+            "{}/{}: section  {}: change  {}: remove  {}: save and leave",
+            key_name(map, Action::PageLeft),
+            key_name(map, Action::PageRight),
+            key_name(map, Action::Confirm),
+            key_name(map, Action::Menu),
+            key_name(map, Action::Back)
+        );
+        d.draw_text(&hint, screen_size.x as i32 - measure_text(&hint, 10) - 10, 20, 10, theme.faded_text());
+
+        for (index, section) in Section::ALL.iter().enumerate() {
+            let bounds = Options::tab_bounds(index);
+            if index == self.section {
+                d.draw_rectangle_rec(bounds, Color::BLUE);
+            }
+            let color = if index == self.section { Color::RAYWHITE } else { theme.text() };
+            d.draw_text(section.title(), bounds.x as i32 + 8, bounds.y as i32 + 6, 20, color);
+        }
+
+        if self.rows.is_empty() {
+            d.draw_text("Nothing here.", TABS_WIDTH + 20, HEADER_HEIGHT + 16, 20, theme.faded_text());
+        }
+        for (index, row) in self.rows.iter().enumerate() {
+            let bounds = match self.row_bounds(index, screen_size) {
+                Some(bounds) => bounds,
+                None => continue,
+            };
+            d.draw_rectangle_rec(bounds, theme.panel());
+            if index == self.row {
+                d.draw_rectangle_lines_ex(bounds, 2, Color::BLUE);
+            }
+            d.draw_text(&row.label, bounds.x as i32 + 8, bounds.y as i32 + 6, 20, theme.text());
+            let value_x = (bounds.x + bounds.width) as i32 - measure_text(&row.value, 20) - 8;
+            d.draw_text(&row.value, value_x, bounds.y as i32 + 6, 20, Color::DARKBLUE);
+        }
+
+        if let Some(editing) = &self.editing {
+            self.draw_editing(d, editing, theme, screen_size);
+        }
+        if let Some(message) = &self.message {
+            d.draw_text(message, TABS_WIDTH + 10, screen_size.y as i32 - 25, 20, theme.faded_text());
+        }
+    }
+
+    fn unload(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread, _context: &mut AppContext) {
+        self.rows.clear();
+    }
+}